@@ -0,0 +1,50 @@
+// mede o Lexer numa fonte grande, como a de um arquivo gerado; para comparar com
+// outra versão: 'cargo bench -- --save-baseline antes' e depois '--baseline antes'
+use std::hint::black_box;
+
+use CompiladorRustC::Lexer;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+// milhares de funções quase iguais, com identificadores, números, literais e
+// comentários, para exercitar todos os caminhos do lexer
+fn fonte_gerada(funcoes: usize) -> String {
+    let mut fonte = String::from("#include <stdio.h>\n#define LIMITE 1024\n\n");
+
+    for i in 0..funcoes {
+        fonte.push_str(&format!(
+            "/* função gerada número {i} */\n\
+             static unsigned long calcular_{i}(const int *valores, int quantidade) {{\n\
+             \x20   unsigned long acumulado = 0x{i:X}UL; // semente\n\
+             \x20   for (int indice = 0; indice < quantidade && indice < LIMITE; ++indice) {{\n\
+             \x20       acumulado += valores[indice] * {i}u + 3.5e-2f;\n\
+             \x20       acumulado ^= acumulado >> 7;\n\
+             \x20   }}\n\
+             \x20   printf(\"resultado %lu\\n\", acumulado);\n\
+             \x20   return acumulado != 'x' ? acumulado : 0;\n\
+             }}\n\n"
+        ));
+    }
+
+    fonte
+}
+
+fn lexer(c: &mut Criterion) {
+    let fonte = fonte_gerada(5_000);
+
+    let mut grupo = c.benchmark_group("lexer");
+    grupo.throughput(Throughput::Bytes(fonte.len() as u64));
+    grupo.sample_size(20);
+
+    grupo.bench_function("tokenizar", |b| {
+        b.iter(|| Lexer::new(black_box(&fonte)).tokenizar().unwrap())
+    });
+    // sem juntar os tokens num Vec
+    grupo.bench_function("iterar", |b| {
+        b.iter(|| Lexer::new(black_box(&fonte)).count())
+    });
+
+    grupo.finish();
+}
+
+criterion_group!(benches, lexer);
+criterion_main!(benches);
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::token::Span;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Severidade {
+    Erro,
+    Aviso,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CodigoErro {
+    // léxicos
+    CharNaoFechado,
+    NumeroInvalido,
+    StringNaoFechada,
+    EscapeInvalido,
+
+    // sintáticos
+    TokenInesperado,
+    PrimarioEsperado,
+    AlvoAtribuicaoInvalido,
+    NomeEsperado,
+    PontoVirgulaEsperado,
+    ParentesisEsperado,
+    ColcheteEsperado,
+    ChaveEsperada,
+    TipoInvalido,
+    ConstanteForaDoIntervalo,
+
+    // pré-processador
+    DiretivaInvalida,
+    MacroRedefinida,
+    ArgumentosMacro,
+    ColagemInvalida,
+    CondicionalDesbalanceado,
+    InclusaoNaoEncontrada,
+    InclusaoCiclica,
+    DiretivaErro,
+    DiretivaAviso,
+
+    // avaliação de expressões constantes
+    DivisaoPorZero,
+    ExpressaoNaoConstante,
+    OperandoInvalido,
+    DeslocamentoInvalido,
+
+    // semânticos
+    BreakForaDeLaco,
+    ContinueForaDeLaco,
+}
+
+impl CodigoErro {
+    pub fn como_str(&self) -> &'static str {
+        match self {
+            CodigoErro::CharNaoFechado => "E0101",
+            CodigoErro::NumeroInvalido => "E0102",
+            CodigoErro::StringNaoFechada => "E0103",
+            CodigoErro::EscapeInvalido => "E0104",
+
+            CodigoErro::TokenInesperado => "E0201",
+            CodigoErro::PrimarioEsperado => "E0202",
+            CodigoErro::AlvoAtribuicaoInvalido => "E0203",
+            CodigoErro::NomeEsperado => "E0204",
+            CodigoErro::PontoVirgulaEsperado => "E0205",
+            CodigoErro::ParentesisEsperado => "E0206",
+            CodigoErro::ColcheteEsperado => "E0207",
+            CodigoErro::ChaveEsperada => "E0208",
+            CodigoErro::TipoInvalido => "E0209",
+            CodigoErro::ConstanteForaDoIntervalo => "E0210",
+
+            CodigoErro::DiretivaInvalida => "E0301",
+            CodigoErro::MacroRedefinida => "E0302",
+            CodigoErro::ArgumentosMacro => "E0303",
+            CodigoErro::ColagemInvalida => "E0304",
+            CodigoErro::CondicionalDesbalanceado => "E0305",
+            CodigoErro::InclusaoNaoEncontrada => "E0306",
+            CodigoErro::InclusaoCiclica => "E0307",
+            CodigoErro::DiretivaErro => "E0308",
+            CodigoErro::DiretivaAviso => "E0309",
+
+            CodigoErro::DivisaoPorZero => "E0401",
+            CodigoErro::ExpressaoNaoConstante => "E0402",
+            CodigoErro::OperandoInvalido => "E0403",
+            CodigoErro::DeslocamentoInvalido => "E0404",
+
+            CodigoErro::BreakForaDeLaco => "E0501",
+            CodigoErro::ContinueForaDeLaco => "E0502",
+        }
+    }
+}
+
+// erro ou aviso reportado ao usuário, no lugar de um panic
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severidade: Severidade,
+    pub codigo: CodigoErro,
+    pub mensagem: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn erro(codigo: CodigoErro, mensagem: String, span: Span) -> Self {
+        Self {
+            severidade: Severidade::Erro,
+            codigo,
+            mensagem,
+            span,
+        }
+    }
+
+    pub fn aviso(codigo: CodigoErro, mensagem: String, span: Span) -> Self {
+        Self {
+            severidade: Severidade::Aviso,
+            codigo,
+            mensagem,
+            span,
+        }
+    }
+
+    // como o Display, mas precedido do arquivo do span; 'arquivos' é a tabela
+    // de Preprocessador::arquivos
+    pub fn formatar(&self, arquivos: &[PathBuf]) -> String {
+        match arquivos.get(self.span.arquivo) {
+            Some(arquivo) => format!("{}: {}", arquivo.display(), self),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severidade = match self.severidade {
+            Severidade::Erro => "erro",
+            Severidade::Aviso => "aviso",
+        };
+
+        write!(
+            f,
+            "{}[{}] na linha {}",
+            severidade,
+            self.codigo.como_str(),
+            self.span.linha
+        )?;
+
+        if self.span.coluna > 0 {
+            write!(f, ", coluna {}", self.span.coluna)?;
+        }
+
+        write!(f, ": {}", self.mensagem)
+    }
+}
@@ -1,28 +1,369 @@
-use crate::parser::{Expr, Operador};
+use serde::Serialize;
 
-pub fn avaliar(expressao: &Expr) -> f64 {
-    match expressao {
-        Expr::Numero(valor) => *valor,
-        Expr::Binario {
+use crate::diagnostico::{CodigoErro, Diagnostic};
+use crate::parser::{Expr, ExprKind, Operador};
+use crate::tipo::{Sinal, Tipo, TipoBase};
+use crate::token::{Span, Token};
+
+// resultado de uma expressão constante; inteiros seguem a aritmética de C (divisão
+// truncada em direção a zero, estouro com wraparound, unsigned vence nas conversões
+// usuais). todo inteiro tem 64 bits, como intmax_t e uintmax_t no '#if': só as
+// conversões explícitas truncam à largura do tipo, então '(unsigned)0 - 1' dá
+// 0xFFFFFFFFFFFFFFFF e não 0xFFFFFFFF
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Valor {
+    Int(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+impl Valor {
+    pub fn e_verdadeiro(&self) -> bool {
+        match self {
+            Valor::Int(v) => *v != 0,
+            Valor::Unsigned(v) => *v != 0,
+            Valor::Float(v) => *v != 0.0,
+        }
+    }
+
+    fn como_float(&self) -> f64 {
+        match self {
+            Valor::Int(v) => *v as f64,
+            Valor::Unsigned(v) => *v as f64,
+            Valor::Float(v) => *v,
+        }
+    }
+
+    // o mesmo padrão de bits, como na conversão de C para unsigned
+    fn como_unsigned(&self) -> u64 {
+        match self {
+            Valor::Int(v) => *v as u64,
+            Valor::Unsigned(v) => *v,
+            Valor::Float(v) => *v as u64,
+        }
+    }
+}
+
+fn booleano(condicao: bool) -> Valor {
+    Valor::Int(condicao as i64)
+}
+
+fn nao_constante(span: Span) -> Diagnostic {
+    Diagnostic::erro(
+        CodigoErro::ExpressaoNaoConstante,
+        "A expressão não é uma constante".to_string(),
+        span,
+    )
+}
+
+pub fn avaliar(expressao: &Expr) -> Result<Valor, Diagnostic> {
+    match &expressao.kind {
+        ExprKind::NumeroInt(valor) => Ok(Valor::Int(*valor)),
+        ExprKind::NumeroUnsigned(valor) => Ok(Valor::Unsigned(*valor)),
+        ExprKind::NumeroFloat(valor) => Ok(Valor::Float(*valor)),
+        ExprKind::CharLiteral(valor, _) => Ok(Valor::Int(*valor as i64)),
+        ExprKind::BoolLiteral(valor) => Ok(Valor::Int(i64::from(*valor))),
+        ExprKind::Agrupamento(expr) => avaliar(expr),
+
+        ExprKind::Unario { operador, direita } => {
+            let valor = avaliar(direita)?;
+
+            match operador {
+                Token::Mais => Ok(valor),
+                Token::Menos => match valor {
+                    Valor::Int(v) => Ok(Valor::Int(v.wrapping_neg())),
+                    Valor::Unsigned(v) => Ok(Valor::Unsigned(v.wrapping_neg())),
+                    Valor::Float(v) => Ok(Valor::Float(-v)),
+                },
+                Token::Negacao => Ok(booleano(!valor.e_verdadeiro())),
+                Token::Til => match valor {
+                    Valor::Int(v) => Ok(Valor::Int(!v)),
+                    Valor::Unsigned(v) => Ok(Valor::Unsigned(!v)),
+                    Valor::Float(_) => Err(Diagnostic::erro(
+                        CodigoErro::OperandoInvalido,
+                        "Operador '~' exige um operando inteiro".to_string(),
+                        expressao.span,
+                    )),
+                },
+                _ => Err(nao_constante(expressao.span)),
+            }
+        }
+
+        ExprKind::Binario {
             esquerda,
             operador,
             direita,
         } => {
-            let valor_esquerda = avaliar(esquerda);
-            let valor_direita = avaliar(direita);
+            let valor_esquerda = avaliar(esquerda)?;
 
+            // && e || não avaliam o lado direito quando o resultado já está decidido
             match operador {
-                Operador::Mais => valor_esquerda + valor_direita,
-                Operador::Menos => valor_esquerda - valor_direita,
-                Operador::Asterisco => valor_esquerda * valor_direita,
-                Operador::Divisao => {
-                    if valor_direita == 0.0 {
-                        panic!("Erro: Divisão por zero");
-                    }
-                    valor_esquerda / valor_direita
+                Operador::EComercialDuplo if !valor_esquerda.e_verdadeiro() => {
+                    return Ok(Valor::Int(0));
+                }
+                Operador::BarraVerticalDupla if valor_esquerda.e_verdadeiro() => {
+                    return Ok(Valor::Int(1));
                 }
+                _ => {}
             }
+
+            let valor_direita = avaliar(direita)?;
+
+            match (valor_esquerda, valor_direita) {
+                (a @ Valor::Float(_), b) | (a, b @ Valor::Float(_)) => avaliar_floats(
+                    a.como_float(),
+                    operador,
+                    b.como_float(),
+                    direita.span,
+                    expressao.span,
+                ),
+                // o deslocamento tem o tipo do operando esquerdo, sem as conversões usuais
+                (a, b)
+                    if matches!(
+                        operador,
+                        Operador::DeslocamentoEsq | Operador::DeslocamentoDir
+                    ) =>
+                {
+                    deslocar(a, operador, b, direita.span)
+                }
+                (Valor::Int(a), Valor::Int(b)) => avaliar_inteiros(a, operador, b, direita.span),
+                (a, b) => {
+                    avaliar_unsigned(a.como_unsigned(), operador, b.como_unsigned(), direita.span)
+                }
+            }
+        }
+
+        // só o ramo escolhido é avaliado, como em '1 ? 2 : 1 / 0'
+        ExprKind::Ternario {
+            condicao,
+            entao,
+            senao,
+        } => {
+            if avaliar(condicao)?.e_verdadeiro() {
+                avaliar(entao)
+            } else {
+                avaliar(senao)
+            }
+        }
+
+        ExprKind::Cast {
+            tipo: Tipo::Base { base, sinal, .. },
+            expressao: operando,
+        } => converter(avaliar(operando)?, base, *sinal, expressao.span),
+
+        _ => Err(nao_constante(expressao.span)),
+    }
+}
+
+// conversão explícita para um tipo aritmético; inteiros são truncados à largura do
+// tipo destino como num alvo x86-64 (char de 8 bits com sinal, int de 32, long de 64)
+fn converter(
+    valor: Valor,
+    base: &TipoBase,
+    sinal: Option<Sinal>,
+    span: Span,
+) -> Result<Valor, Diagnostic> {
+    let bits = match base {
+        TipoBase::Float | TipoBase::Double | TipoBase::LongDouble => {
+            return Ok(Valor::Float(valor.como_float()));
         }
-        Expr::Agrupamento(expr) => avaliar(expr),
+        TipoBase::Bool => return Ok(booleano(valor.e_verdadeiro())),
+        TipoBase::Char => 8,
+        TipoBase::Short => 16,
+        TipoBase::Int | TipoBase::Enum(_) => 32,
+        TipoBase::Long | TipoBase::LongLong => 64,
+        _ => return Err(nao_constante(span)),
+    };
+
+    let unsigned = sinal == Some(Sinal::Unsigned);
+    let inteiro = match valor {
+        Valor::Int(v) => v,
+        Valor::Unsigned(v) => v as i64,
+        Valor::Float(v) if unsigned => v as u64 as i64,
+        Valor::Float(v) => v as i64,
+    };
+
+    let deslocamento = 64 - bits;
+    if !unsigned {
+        return Ok(Valor::Int((inteiro << deslocamento) >> deslocamento));
+    }
+
+    let convertido = (inteiro as u64) << deslocamento >> deslocamento;
+    // unsigned char e unsigned short são promovidos a int
+    if bits < 32 {
+        Ok(Valor::Int(convertido as i64))
+    } else {
+        Ok(Valor::Unsigned(convertido))
     }
 }
+
+// '<<' e '>>'; o resultado tem o tipo do operando esquerdo e '>>' de um unsigned
+// preenche com zeros
+fn deslocar(
+    valor: Valor,
+    operador: &Operador,
+    quantidade: Valor,
+    span_direita: Span,
+) -> Result<Valor, Diagnostic> {
+    let quantidade = match quantidade {
+        Valor::Int(v) => i128::from(v),
+        Valor::Unsigned(v) => i128::from(v),
+        Valor::Float(_) => unreachable!("floats são tratados em avaliar_floats"),
+    };
+    if !(0..64).contains(&quantidade) {
+        return Err(Diagnostic::erro(
+            CodigoErro::DeslocamentoInvalido,
+            format!(
+                "Deslocamento de {} bits fora do intervalo permitido",
+                quantidade
+            ),
+            span_direita,
+        ));
+    }
+    let quantidade = quantidade as u32;
+
+    let esquerda = matches!(operador, Operador::DeslocamentoEsq);
+    Ok(match valor {
+        Valor::Int(v) if esquerda => Valor::Int(v.wrapping_shl(quantidade)),
+        Valor::Int(v) => Valor::Int(v >> quantidade),
+        Valor::Unsigned(v) if esquerda => Valor::Unsigned(v << quantidade),
+        Valor::Unsigned(v) => Valor::Unsigned(v >> quantidade),
+        Valor::Float(_) => unreachable!("floats são tratados em avaliar_floats"),
+    })
+}
+
+fn avaliar_inteiros(
+    a: i64,
+    operador: &Operador,
+    b: i64,
+    span_direita: Span,
+) -> Result<Valor, Diagnostic> {
+    let valor = match operador {
+        Operador::Mais => a.wrapping_add(b),
+        Operador::Menos => a.wrapping_sub(b),
+        Operador::Asterisco => a.wrapping_mul(b),
+        Operador::Divisao | Operador::Modulo => {
+            if b == 0 {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DivisaoPorZero,
+                    "Erro: Divisão por zero".to_string(),
+                    span_direita,
+                ));
+            }
+            if let Operador::Divisao = operador {
+                a.wrapping_div(b)
+            } else {
+                a.wrapping_rem(b)
+            }
+        }
+        Operador::DeslocamentoEsq | Operador::DeslocamentoDir => {
+            unreachable!("deslocamentos são tratados em deslocar")
+        }
+        Operador::EComercial => a & b,
+        Operador::BarraVertical => a | b,
+        Operador::Xor => a ^ b,
+        Operador::Comparar => (a == b) as i64,
+        Operador::Diferente => (a != b) as i64,
+        Operador::Maior => (a > b) as i64,
+        Operador::Menor => (a < b) as i64,
+        Operador::MaiorOuIgual => (a >= b) as i64,
+        Operador::MenorOuIgual => (a <= b) as i64,
+        Operador::EComercialDuplo => (a != 0 && b != 0) as i64,
+        Operador::BarraVerticalDupla => (a != 0 || b != 0) as i64,
+    };
+
+    Ok(Valor::Int(valor))
+}
+
+// como avaliar_inteiros, depois de um dos lados ter convertido o outro para unsigned;
+// comparações e operadores lógicos continuam dando int
+fn avaliar_unsigned(
+    a: u64,
+    operador: &Operador,
+    b: u64,
+    span_direita: Span,
+) -> Result<Valor, Diagnostic> {
+    let valor = match operador {
+        Operador::Mais => a.wrapping_add(b),
+        Operador::Menos => a.wrapping_sub(b),
+        Operador::Asterisco => a.wrapping_mul(b),
+        Operador::Divisao | Operador::Modulo => {
+            if b == 0 {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DivisaoPorZero,
+                    "Erro: Divisão por zero".to_string(),
+                    span_direita,
+                ));
+            }
+            if let Operador::Divisao = operador {
+                a / b
+            } else {
+                a % b
+            }
+        }
+        Operador::DeslocamentoEsq | Operador::DeslocamentoDir => {
+            unreachable!("deslocamentos são tratados em deslocar")
+        }
+        Operador::EComercial => a & b,
+        Operador::BarraVertical => a | b,
+        Operador::Xor => a ^ b,
+        Operador::Comparar => return Ok(booleano(a == b)),
+        Operador::Diferente => return Ok(booleano(a != b)),
+        Operador::Maior => return Ok(booleano(a > b)),
+        Operador::Menor => return Ok(booleano(a < b)),
+        Operador::MaiorOuIgual => return Ok(booleano(a >= b)),
+        Operador::MenorOuIgual => return Ok(booleano(a <= b)),
+        Operador::EComercialDuplo => return Ok(booleano(a != 0 && b != 0)),
+        Operador::BarraVerticalDupla => return Ok(booleano(a != 0 || b != 0)),
+    };
+
+    Ok(Valor::Unsigned(valor))
+}
+
+fn avaliar_floats(
+    a: f64,
+    operador: &Operador,
+    b: f64,
+    span_direita: Span,
+    span: Span,
+) -> Result<Valor, Diagnostic> {
+    let valor = match operador {
+        Operador::Mais => Valor::Float(a + b),
+        Operador::Menos => Valor::Float(a - b),
+        Operador::Asterisco => Valor::Float(a * b),
+        Operador::Divisao => {
+            if b == 0.0 {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DivisaoPorZero,
+                    "Erro: Divisão por zero".to_string(),
+                    span_direita,
+                ));
+            }
+            Valor::Float(a / b)
+        }
+        Operador::Comparar => booleano(a == b),
+        Operador::Diferente => booleano(a != b),
+        Operador::Maior => booleano(a > b),
+        Operador::Menor => booleano(a < b),
+        Operador::MaiorOuIgual => booleano(a >= b),
+        Operador::MenorOuIgual => booleano(a <= b),
+        Operador::EComercialDuplo => booleano(a != 0.0 && b != 0.0),
+        Operador::BarraVerticalDupla => booleano(a != 0.0 || b != 0.0),
+
+        // %, &, |, ^, << e >> só existem para inteiros em C
+        Operador::Modulo
+        | Operador::EComercial
+        | Operador::BarraVertical
+        | Operador::Xor
+        | Operador::DeslocamentoEsq
+        | Operador::DeslocamentoDir => {
+            return Err(Diagnostic::erro(
+                CodigoErro::OperandoInvalido,
+                format!("Operador {:?} exige operandos inteiros", operador),
+                span,
+            ));
+        }
+    };
+
+    Ok(valor)
+}
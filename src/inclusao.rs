@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// de onde o Preprocessador lê os arquivos de '#include'; o Tauri e os testes podem
+// servir os arquivos da memória em vez do disco
+pub trait SistemaArquivos {
+    // None quando o arquivo não existe ou não pode ser lido
+    fn ler(&self, caminho: &Path) -> Option<String>;
+}
+
+pub struct SistemaArquivosDisco;
+
+impl SistemaArquivos for SistemaArquivosDisco {
+    fn ler(&self, caminho: &Path) -> Option<String> {
+        fs::read_to_string(caminho).ok()
+    }
+}
+
+#[derive(Default)]
+pub struct SistemaArquivosMemoria {
+    arquivos: HashMap<PathBuf, String>,
+}
+
+impl SistemaArquivosMemoria {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inserir(&mut self, caminho: impl AsRef<Path>, conteudo: impl Into<String>) {
+        self.arquivos
+            .insert(normalizar(caminho.as_ref()), conteudo.into());
+    }
+}
+
+impl SistemaArquivos for SistemaArquivosMemoria {
+    fn ler(&self, caminho: &Path) -> Option<String> {
+        self.arquivos.get(&normalizar(caminho)).cloned()
+    }
+}
+
+// remove '.' e resolve '..' sem consultar o disco, para que 'inc/../a.h' e './a.h'
+// sejam reconhecidos como o mesmo arquivo
+pub fn normalizar(caminho: &Path) -> PathBuf {
+    let mut normalizado = PathBuf::new();
+
+    for componente in caminho.components() {
+        match componente {
+            Component::CurDir => {}
+            // '..' no começo (ou depois de outro '..') não tem o que desfazer
+            Component::ParentDir => {
+                if normalizado.file_name().is_some() {
+                    normalizado.pop();
+                } else {
+                    normalizado.push("..");
+                }
+            }
+            outro => normalizado.push(outro),
+        }
+    }
+
+    normalizado
+}
@@ -1,380 +1,1141 @@
-use crate::token::Token;
+use std::borrow::Cow;
 
-// analisador léxico
-pub struct Lexer {
-    fonte: Vec<char>,
-    posicao: usize,
+use crate::diagnostico::{CodigoErro, Diagnostic};
+use crate::token::{
+    Base, Dialeto, LiteralNumero, PalavraChave, Prefixo, Span, SpannedToken, TipoTrivia, Token,
+    TokenComTrivia, Trivia,
+};
+
+// analisador léxico; percorre a fonte em bytes e empresta dela o texto dos tokens.
+// as fases 1 e 2 da tradução (trígrafos e continuações de linha) são feitas ao ler
+// cada caractere, então os spans continuam apontando para a fonte original
+pub struct Lexer<'a> {
+    fonte: &'a str,
+    // bytes [byte, fim_atual) do caractere atual; um trígrafo ocupa três
+    byte: usize,
+    fim_atual: usize,
+    // fim do último caractere consumido, antes de alguma continuação de linha
+    fim_anterior: usize,
     caractere_atual: char,
     linha: usize,
+    coluna: usize,
+    // dentro de uma diretiva a quebra de linha vira token, porque marca o fim dela;
+    // no resto do código é só espaço
+    em_diretiva: bool,
+    // só um '#' no começo da linha abre diretiva; no meio dela é '#' ou '##' de macro
+    inicio_de_linha: bool,
+    // depois de '#error' e '#warning' o resto da linha é a mensagem, sem virar tokens
+    mensagem_diretiva: bool,
+    // arquivo de onde a fonte veio, gravado em todos os spans
+    arquivo: usize,
+    // decide quais nomes viram Token::PalavraChave, e em C++ quais viram operador ('and')
+    dialeto: Dialeto,
+    // '??=' e os outros trígrafos só são trocados quando pedido
+    trigrafos: bool,
+    // só no modo sem perdas: trivia pulada desde o último token
+    trivia: Option<Vec<Trivia<'a>>>,
+    // byte onde começa o token sendo lido, para guardar o texto de um literal com erro
+    inicio_token: usize,
+    // erro que interrompeu a iteração
+    erro: Option<Diagnostic>,
+    // o iterador acaba depois do Token::Fundo ou de um erro
+    terminado: bool,
 }
 
-impl Lexer {
-    // construtor
-    pub fn new(codigo_fonte: String) -> Self {
-        let fonte: Vec<char> = codigo_fonte.chars().collect();
-        let caractere_atual = if fonte.is_empty() { '\0' } else { fonte[0] };
-
-        Self {
-            fonte,
-            posicao: 0,
-            caractere_atual,
+impl<'a> Lexer<'a> {
+    // construtor; lê a fonte como C17. código C++ ('using', 'class', 'nullptr'...) precisa
+    // de com_dialeto(Dialeto::Cpp), e do Preprocessador::definir_dialeto se ele for usado
+    pub fn new(codigo_fonte: &'a str) -> Self {
+        let mut lexer = Self {
+            fonte: codigo_fonte,
+            byte: 0,
+            fim_atual: 0,
+            fim_anterior: 0,
+            caractere_atual: '\0',
             linha: 1,
-        }
+            coluna: 1,
+            em_diretiva: false,
+            inicio_de_linha: true,
+            mensagem_diretiva: false,
+            arquivo: 0,
+            dialeto: Dialeto::C,
+            trigrafos: false,
+            trivia: None,
+            inicio_token: 0,
+            erro: None,
+            terminado: false,
+        };
+        lexer.carregar(0);
+        lexer
+    }
+
+    // lexer de um arquivo incluído, identificado pelo índice dado pelo Preprocessador
+    pub fn no_arquivo(mut self, arquivo: usize) -> Self {
+        self.arquivo = arquivo;
+        self
+    }
+
+    // sem esta chamada as palavras só de C++ viram Token::Identificador
+    pub fn com_dialeto(mut self, dialeto: Dialeto) -> Self {
+        self.dialeto = dialeto;
+        self
+    }
+
+    // o primeiro caractere já foi lido, então ele é relido com a nova regra
+    pub fn com_trigrafos(mut self, ativos: bool) -> Self {
+        self.trigrafos = ativos;
+        self.linha = 1;
+        self.coluna = 1;
+        self.carregar(0);
+        self
     }
 
     // avança para o próximo caractere na fonte
     pub fn avancar(&mut self) {
-        self.posicao += 1;
-
         if self.caractere_atual == '\n' {
             self.linha += 1;
+            self.coluna = 1;
+            self.inicio_de_linha = true;
+        } else if self.fim_atual - self.byte > self.caractere_atual.len_utf8() {
+            // trígrafo
+            self.coluna += self.fim_atual - self.byte;
+        } else {
+            self.coluna += 1;
         }
 
-        if self.posicao < self.fonte.len() {
-            self.caractere_atual = self.fonte[self.posicao];
-        } else {
-            self.caractere_atual = '\0';
+        self.fim_anterior = self.fim_atual;
+        self.carregar(self.fim_atual);
+    }
+
+    // torna atual o caractere lógico a partir do byte, contando as linhas das
+    // continuações puladas no caminho
+    fn carregar(&mut self, byte: usize) {
+        let (caractere, inicio, fim) = self.caractere_logico(byte);
+
+        if inicio > byte {
+            self.linha += self.fonte[byte..inicio].matches('\n').count();
+            self.coluna = 1;
+        }
+
+        self.caractere_atual = caractere;
+        self.byte = inicio;
+        self.fim_atual = fim;
+    }
+
+    // o caractere que começa no byte, depois das fases 1 e 2: pula as continuações
+    // de linha ('\\' seguida de quebra) e troca os trígrafos; devolve também os
+    // bytes [inicio, fim) dele, e '\0' depois do fim da fonte
+    fn caractere_logico(&self, mut byte: usize) -> (char, usize, usize) {
+        loop {
+            let (caractere, tamanho) = self.caractere_fisico(byte);
+
+            if caractere == '\\' {
+                let depois = byte + tamanho;
+                let quebra = match self.fonte.as_bytes().get(depois..) {
+                    Some([b'\n', ..]) => 1,
+                    Some([b'\r', b'\n', ..]) => 2,
+                    _ => 0,
+                };
+                if quebra > 0 {
+                    byte = depois + quebra;
+                    continue;
+                }
+            }
+
+            return (caractere, byte, byte + tamanho);
+        }
+    }
+
+    // o caractere da fonte no byte e quantos bytes ele ocupa, com os trígrafos já
+    // trocados
+    fn caractere_fisico(&self, byte: usize) -> (char, usize) {
+        let bytes = self.fonte.as_bytes();
+
+        if self.trigrafos
+            && bytes.get(byte..byte + 2) == Some(b"??")
+            && let Some(caractere) = bytes.get(byte + 2).and_then(|&b| trigrafo(b))
+        {
+            return (caractere, 3);
+        }
+
+        match bytes.get(byte) {
+            Some(&b) if b.is_ascii() => (b as char, 1),
+            Some(_) => {
+                let caractere = self.fonte[byte..].chars().next().unwrap_or('\0');
+                (caractere, caractere.len_utf8())
+            }
+            None => ('\0', 0),
         }
     }
 
+    // o caractere 'passos' à frente do atual, sem avançar a posição
+    fn espiar(&self, passos: usize) -> char {
+        let mut caractere = self.caractere_atual;
+        let mut fim = self.fim_atual;
+
+        for _ in 0..passos {
+            let (proximo, _, fim_proximo) = self.caractere_logico(fim);
+            caractere = proximo;
+            fim = fim_proximo;
+        }
+
+        caractere
+    }
+
     // espiadinha retorna o próximo caractere sem avançar a posição
     pub fn espiadinha(&self) -> char {
-        if self.posicao + 1 < self.fonte.len() {
-            self.fonte[self.posicao + 1]
-        } else {
-            '\0'
+        self.espiar(1)
+    }
+
+    pub fn espiar_dois_passos(&self) -> char {
+        self.espiar(2)
+    }
+
+    // o texto lógico entre os bytes, sem as continuações de linha e com os trígrafos
+    // trocados; sem nenhum dos dois é emprestado da fonte
+    fn texto_entre(&self, inicio: usize, fim: usize) -> Cow<'a, str> {
+        if fim <= inicio {
+            return Cow::Borrowed("");
+        }
+
+        let fatia = &self.fonte[inicio..fim];
+        let tem_fase_1_ou_2 = fatia.contains('\\') || (self.trigrafos && fatia.contains("??"));
+        if !tem_fase_1_ou_2 {
+            return Cow::Borrowed(fatia);
         }
+
+        let mut texto = String::with_capacity(fatia.len());
+        let mut byte = inicio;
+        loop {
+            let (caractere, inicio_caractere, fim_caractere) = self.caractere_logico(byte);
+            if inicio_caractere >= fim {
+                break;
+            }
+            texto.push(caractere);
+            byte = fim_caractere;
+        }
+
+        Cow::Owned(texto)
     }
 
     // lê um identificador
-    pub fn ler_identificador(&mut self) -> String {
-        let posicao = self.posicao;
+    pub fn ler_identificador(&mut self) -> Cow<'a, str> {
+        let inicio = self.byte;
 
         while self.caractere_atual.is_alphanumeric() || self.caractere_atual == '_' {
             self.avancar()
         }
 
-        self.fonte[posicao..self.posicao].iter().collect()
+        self.texto_entre(inicio, self.fim_anterior)
     }
 
-    // lê um número
-    pub fn ler_numero(&mut self) -> Token {
-        let posicao = self.posicao;
-        let mut is_float = false;
+    // lê um literal numérico: decimal, octal ('0755'), hexadecimal ('0xFF', '0x1p-3'),
+    // binário ('0b1010'), com fração, expoente e sufixo ('.5', '1e-9', '10UL', '3.0f')
+    pub fn ler_numero(&mut self) -> Result<Token<'a>, Diagnostic> {
+        let inicio = self.byte;
+        let linha = self.linha;
+        let coluna = self.coluna;
 
-        while self.caractere_atual.is_digit(10) {
-            self.avancar()
+        // como o pp-number do padrão: consome tudo que pode fazer parte do número e
+        // depois confere se forma um literal válido ('1e+5', mas também '0xE+1')
+        loop {
+            let c = self.caractere_atual;
+            if matches!(c, 'e' | 'E' | 'p' | 'P') && matches!(self.espiadinha(), '+' | '-') {
+                self.avancar();
+                self.avancar();
+            } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                self.avancar();
+            } else {
+                break;
+            }
         }
 
-        if self.caractere_atual == '.' {
-            is_float = true;
-            self.avancar();
+        let fim = self.fim_anterior;
+        classificar_numero(self.texto_entre(inicio, fim)).map_err(|mensagem| {
+            Diagnostic::erro(
+                CodigoErro::NumeroInvalido,
+                mensagem,
+                Span::new(inicio, fim, linha, coluna).no_arquivo(self.arquivo),
+            )
+        })
+    }
 
-            while self.caractere_atual.is_digit(10) {
-                self.avancar()
-            }
-        }
+    // lê uma string entre aspas
+    pub fn ler_texto(&mut self, prefixo: Prefixo) -> Result<Cow<'a, str>, Diagnostic> {
+        self.ler_literal('"', prefixo)
+    }
+
+    // lê um caractere entre aspas simples
+    pub fn ler_char(&mut self, prefixo: Prefixo) -> Result<char, Diagnostic> {
+        let inicio = self.byte;
+        let linha = self.linha;
+        let coluna = self.coluna;
 
-        let numero_str: String = self.fonte[posicao..self.posicao].iter().collect();
+        let conteudo = self.ler_literal('\'', prefixo)?;
+        let mut chars = conteudo.chars();
 
-        if is_float {
-            Token::NumeroFloat(numero_str)
-        } else {
-            Token::NumeroInt(numero_str)
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => Err(Diagnostic::erro(
+                CodigoErro::CharNaoFechado,
+                "Char literal vazio.".to_string(),
+                Span::new(inicio, self.fim_atual, linha, coluna).no_arquivo(self.arquivo),
+            )),
+            _ => Err(Diagnostic::erro(
+                CodigoErro::CharNaoFechado,
+                "Char literal não fechado ou longo demais.".to_string(),
+                Span::new(inicio, self.fim_atual, linha, coluna).no_arquivo(self.arquivo),
+            )),
         }
     }
 
-    // lê uma string entre aspas
-    pub fn ler_texto(&mut self) -> String {
-        let mut chars: Vec<char> = Vec::new();
+    // lê o conteúdo entre o delimitador de abertura e o de fechamento, resolvendo as
+    // sequências de escape; para no delimitador final, que é consumido por ler_token.
+    // sem escapes o conteúdo é emprestado da fonte. com erro também para no delimitador,
+    // ou na quebra de linha se o literal não foi fechado
+    fn ler_literal(
+        &mut self,
+        delimitador: char,
+        prefixo: Prefixo,
+    ) -> Result<Cow<'a, str>, Diagnostic> {
+        let inicio = self.byte;
+        let linha = self.linha;
+        let coluna = self.coluna;
+        // só é criado no primeiro escape
+        let mut conteudo: Option<String> = None;
+        // o primeiro escape inválido; o literal é lido até o fim mesmo assim
+        let mut erro: Option<Diagnostic> = None;
 
+        self.avancar();
+        let inicio_conteudo = self.byte;
         loop {
-            self.avancar();
-
-            if self.caractere_atual == '\\' {
-                self.avancar();
-                match self.caractere_atual {
-                    'n' => chars.push('\n'),
-                    't' => chars.push('\t'),
-                    '"' => chars.push('"'),
-                    '\\' => chars.push('\\'),
-                    _ => chars.push(self.caractere_atual),
+            match self.caractere_atual {
+                c if c == delimitador => break,
+                '\\' => {
+                    let inicio_escape = self.fim_anterior;
+                    match self.ler_escape(prefixo) {
+                        Ok(c) => conteudo
+                            .get_or_insert_with(|| {
+                                self.texto_entre(inicio_conteudo, inicio_escape)
+                                    .into_owned()
+                            })
+                            .push(c),
+                        Err(diagnostico) => {
+                            erro.get_or_insert(diagnostico);
+                        }
+                    }
+                }
+                // a quebra de linha não pode aparecer crua dentro do literal
+                '\n' | '\0' => {
+                    let (codigo, mensagem) = if delimitador == '"' {
+                        (CodigoErro::StringNaoFechada, "String literal não fechada.")
+                    } else {
+                        (CodigoErro::CharNaoFechado, "Char literal não fechado.")
+                    };
+                    return Err(Diagnostic::erro(
+                        codigo,
+                        mensagem.to_string(),
+                        Span::new(inicio, self.fim_anterior, linha, coluna)
+                            .no_arquivo(self.arquivo),
+                    ));
+                }
+                c => {
+                    if let Some(conteudo) = &mut conteudo {
+                        conteudo.push(c);
+                    }
+                    self.avancar();
                 }
-                continue;
-            }
-
-            if self.caractere_atual == '"' || self.caractere_atual == '\0' {
-                break;
             }
+        }
 
-            chars.push(self.caractere_atual)
+        if let Some(erro) = erro {
+            return Err(erro);
         }
 
-        return chars.iter().collect();
+        Ok(match conteudo {
+            Some(conteudo) => Cow::Owned(conteudo),
+            None => self.texto_entre(inicio_conteudo, self.fim_anterior),
+        })
     }
 
-    // lê um caractere entre aspas simples
-    pub fn ler_char(&mut self) -> char {
+    // lê uma sequência de escape a partir da '\\' e devolve o caractere que ela representa
+    fn ler_escape(&mut self, prefixo: Prefixo) -> Result<char, Diagnostic> {
+        let inicio = self.byte;
+        let linha = self.linha;
+        let coluna = self.coluna;
         self.avancar();
 
-        let c: char;
-        if self.caractere_atual == '\\' {
+        let simples = match self.caractere_atual {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'a' => Some('\x07'),
+            'b' => Some('\x08'),
+            'f' => Some('\x0C'),
+            'v' => Some('\x0B'),
+            '\\' | '\'' | '"' | '?' => Some(self.caractere_atual),
+            _ => None,
+        };
+        if let Some(c) = simples {
             self.avancar();
-            c = match self.caractere_atual {
-                'n' => '\n',
-                't' => '\t',
-                '\'' => '\'',
-                '\\' => '\\',
-                _ => self.caractere_atual,
-            };
+            return Ok(c);
+        }
+
+        // '\123' tem até 3 dígitos octais, '\x' quantos dígitos houver, '\u' exatamente
+        // 4 e '\U' exatamente 8
+        let (radix, minimo, maximo) = match self.caractere_atual {
+            '0'..='7' => (8, 1, 3),
+            'x' => (16, 1, usize::MAX),
+            'u' => (16, 4, 4),
+            'U' => (16, 8, 8),
+            _ => (0, 0, 0),
+        };
+        let letra = self.caractere_atual;
+        if radix == 16 {
             self.avancar();
-        } else {
-            c = self.caractere_atual;
+        }
+
+        let mut valor: u32 = 0;
+        let mut digitos = 0;
+        let mut excedeu = false;
+        while radix != 0 && digitos < maximo {
+            let Some(digito) = self.caractere_atual.to_digit(radix) else {
+                break;
+            };
+            match valor.checked_mul(radix).and_then(|v| v.checked_add(digito)) {
+                Some(novo) => valor = novo,
+                None => excedeu = true,
+            }
+            digitos += 1;
             self.avancar();
         }
 
-        if self.caractere_atual != '\'' {
-            panic!(
-                "Erro Léxico na linha {}: Char literal não fechado ou longo demais.",
-                self.linha
-            );
+        let span = Span::new(inicio, self.fim_anterior, linha, coluna).no_arquivo(self.arquivo);
+        if radix == 0 {
+            return Err(Diagnostic::erro(
+                CodigoErro::EscapeInvalido,
+                format!("Sequência de escape '\\{}' desconhecida", letra),
+                span,
+            ));
+        }
+        if digitos < minimo {
+            return Err(Diagnostic::erro(
+                CodigoErro::EscapeInvalido,
+                format!("Sequência de escape '\\{}' com dígitos faltando", letra),
+                span,
+            ));
         }
 
-        return c;
+        // '\777' não cabe num char; '\u' e '\U' são caracteres, não unidades de código
+        if matches!(letra, '0'..='7' | 'x') && (excedeu || valor > prefixo.unidade_maxima()) {
+            return Err(Diagnostic::erro(
+                CodigoErro::EscapeInvalido,
+                format!(
+                    "Sequência de escape com valor {:#x} não cabe numa unidade do literal",
+                    valor
+                ),
+                span,
+            ));
+        }
+
+        match char::from_u32(valor).filter(|_| !excedeu) {
+            Some(c) => Ok(c),
+            None => Err(Diagnostic::erro(
+                CodigoErro::EscapeInvalido,
+                format!("Sequência de escape com valor inválido: {:#x}", valor),
+                span,
+            )),
+        }
+    }
+
+    // 'L', 'u8', 'u' e 'U' colados a um literal são prefixos, não identificadores;
+    // consome o prefixo e deixa o lexer na aspa
+    fn ler_prefixo_literal(&mut self) -> Option<Prefixo> {
+        let aspa = |c: char| c == '"' || c == '\'';
+
+        let (prefixo, tamanho) = match (self.caractere_atual, self.espiadinha()) {
+            ('u', '8') if aspa(self.espiar_dois_passos()) => (Prefixo::Utf8, 2),
+            ('L', c) if aspa(c) => (Prefixo::Largo, 1),
+            ('u', c) if aspa(c) => (Prefixo::Utf16, 1),
+            ('U', c) if aspa(c) => (Prefixo::Utf32, 1),
+            _ => return None,
+        };
+
+        for _ in 0..tamanho {
+            self.avancar();
+        }
+        Some(prefixo)
     }
 
-    pub fn ler_diretiva_pre_processador(&mut self) -> Token {
+    pub fn ler_diretiva_pre_processador(&mut self) -> Token<'a> {
         self.avancar();
 
-        while self.caractere_atual.is_whitespace() {
+        while self.caractere_atual == ' ' || self.caractere_atual == '\t' {
             self.avancar();
         }
 
         let comando = self.ler_identificador();
 
         if comando == "include" {
-            while self.caractere_atual.is_whitespace() {
+            while self.caractere_atual == ' ' || self.caractere_atual == '\t' {
                 self.avancar();
             }
 
             if self.caractere_atual == '<' {
                 let path = self.ler_path_delimitado('>');
-                return Token::InclusaoGlobal(path);
+                Token::InclusaoGlobal(path)
             } else if self.caractere_atual == '"' {
                 let path = self.ler_path_delimitado('"');
-                return Token::InclusaoLocal(path);
+                Token::InclusaoLocal(path)
             } else {
-                return Token::Invalido;
+                Token::Invalido
             }
         } else {
-            return Token::Diretiva(comando);
+            self.em_diretiva = true;
+            self.mensagem_diretiva = comando == "error" || comando == "warning";
+            Token::Diretiva(comando)
         }
     }
 
-    pub fn ler_path_delimitado(&mut self, delimitador: char) -> String {
-        let posicao_inicial = self.posicao + 1;
+    pub fn ler_path_delimitado(&mut self, delimitador: char) -> Cow<'a, str> {
+        self.avancar();
+        let inicio = self.byte;
 
-        loop {
+        while self.caractere_atual != delimitador && self.caractere_atual != '\0' {
+            self.avancar();
+        }
+
+        let path = self.texto_entre(inicio, self.fim_anterior);
+
+        self.avancar();
+
+        path
+    }
+
+    // o texto até o fim da linha, como um Token::Texto; apóstrofos e aspas soltos
+    // são comuns em mensagens de '#error'
+    fn ler_mensagem_diretiva(&mut self) -> Token<'a> {
+        let inicio = self.byte;
+
+        while self.caractere_atual != '\n' && self.caractere_atual != '\0' {
             self.avancar();
-            if self.caractere_atual == delimitador || self.caractere_atual == '\0' {
+        }
+
+        let mensagem = match self.texto_entre(inicio, self.fim_anterior) {
+            Cow::Borrowed(mensagem) => Cow::Borrowed(mensagem.trim_end()),
+            Cow::Owned(mensagem) => Cow::Owned(mensagem.trim_end().to_string()),
+        };
+        Token::Texto(mensagem, Prefixo::Nenhum)
+    }
+
+    // lê todos os tokens até o fim da fonte, incluindo o Token::Fundo
+    pub fn tokenizar(&mut self) -> Result<Vec<SpannedToken<'a>>, Diagnostic> {
+        let tokens = self.by_ref().collect();
+
+        match self.erro.take() {
+            Some(diagnostico) => Err(diagnostico),
+            None => Ok(tokens),
+        }
+    }
+
+    // o erro léxico que encerrou a iteração, se houve
+    pub fn erro(&self) -> Option<&Diagnostic> {
+        self.erro.as_ref()
+    }
+
+    // como tokenizar, mas cada token leva o texto original e a trivia (espaços, quebras
+    // de linha e comentários) em volta, para ferramentas que precisam refazer a fonte
+    pub fn tokenizar_com_trivia(&mut self) -> Result<Vec<TokenComTrivia<'a>>, Diagnostic> {
+        let mut tokens: Vec<TokenComTrivia<'a>> = Vec::new();
+        self.trivia = Some(Vec::new());
+
+        loop {
+            let token = match self.prox_token() {
+                Ok(token) => token,
+                Err(diagnostico) => {
+                    self.trivia = None;
+                    return Err(diagnostico);
+                }
+            };
+            let mut antes = self.trivia.replace(Vec::new()).unwrap_or_default();
+
+            // até a primeira quebra de linha, a trivia é do token anterior
+            if let Some(anterior) = tokens.last_mut() {
+                let fim_da_linha = antes
+                    .iter()
+                    .position(|trivia| trivia.tipo == TipoTrivia::QuebraLinha)
+                    .map_or(antes.len(), |i| i + 1);
+                anterior.depois = antes.drain(..fim_da_linha).collect();
+            }
+
+            let fim = token.token == Token::Fundo;
+            let texto = &self.fonte[token.span.inicio..token.span.fim];
+            tokens.push(TokenComTrivia {
+                token,
+                texto,
+                antes,
+                depois: Vec::new(),
+            });
+            if fim {
                 break;
             }
         }
 
-        let path: String = self.fonte[posicao_inicial..self.posicao].iter().collect();
+        self.trivia = None;
+        Ok(tokens)
+    }
 
-        self.avancar();
+    pub fn prox_token(&mut self) -> Result<SpannedToken<'a>, Diagnostic> {
+        let (inicio, linha, coluna) = self.pular_espacos_e_comentarios();
+        self.inicio_token = inicio;
+        let inicio_de_linha = std::mem::replace(&mut self.inicio_de_linha, false);
+        let token = if std::mem::take(&mut self.mensagem_diretiva)
+            && !matches!(self.caractere_atual, '\n' | '\0')
+        {
+            self.ler_mensagem_diretiva()
+        } else {
+            self.ler_token(inicio_de_linha)?
+        };
 
-        return path;
+        Ok(SpannedToken {
+            token,
+            span: Span::new(inicio, self.fim_anterior, linha, coluna).no_arquivo(self.arquivo),
+        })
     }
 
-    pub fn prox_token(&mut self) -> (Token, usize) {
+    // pula espaços e comentários, devolvendo onde o próximo token começa; a quebra de
+    // linha que fecha uma diretiva não é pulada
+    fn pular_espacos_e_comentarios(&mut self) -> (usize, usize, usize) {
         loop {
-            // pula espaços em branco
-            while self.caractere_atual.is_whitespace() && self.caractere_atual != '\n' {
-                self.avancar();
+            // continuação de linha entre o último caractere consumido e o atual; no
+            // meio de um token ou de outra trivia ela fica no texto deles
+            if self.fim_anterior < self.byte
+                && let Some(trivia) = &mut self.trivia
+            {
+                let texto = &self.fonte[self.fim_anterior..self.byte];
+                let linha = self.linha - texto.matches('\n').count();
+                let inicio_linha = self.fonte[..self.fim_anterior]
+                    .rfind('\n')
+                    .map_or(0, |i| i + 1);
+                let coluna = self.fonte[inicio_linha..self.fim_anterior].chars().count() + 1;
+
+                trivia.push(Trivia {
+                    tipo: TipoTrivia::Continuacao,
+                    texto,
+                    span: Span::new(self.fim_anterior, self.byte, linha, coluna)
+                        .no_arquivo(self.arquivo),
+                });
+                self.fim_anterior = self.byte;
             }
 
-            let linha_token = self.linha;
-
-            // determina o tipo de token com base no caractere atual
-            let token = match self.caractere_atual {
-                '\n' => Token::QuebraLinha,
-                ';' => Token::PontoVirgula,
-                '(' => Token::AbreParentesis,
-                ')' => Token::FechaParentesis,
-                '[' => Token::AbreColchete,
-                ']' => Token::FechaColchete,
-                '{' => Token::AbreChave,
-                '}' => Token::FechaChave,
-                '.' => Token::Ponto,
-                ',' => Token::Virgula,
-
-                '\'' => {
-                    let conteudo_char = self.ler_char();
-                    Token::ConteudoChar(conteudo_char)
+            let inicio = (self.byte, self.linha, self.coluna);
+
+            let quebra_linha = self.caractere_atual == '\n'
+                || (self.caractere_atual == '\r' && self.espiadinha() == '\n');
+
+            let tipo = if quebra_linha && !self.em_diretiva {
+                if self.caractere_atual == '\r' {
+                    self.avancar();
+                }
+                self.avancar();
+                TipoTrivia::QuebraLinha
+            } else if self.caractere_atual.is_whitespace() && self.caractere_atual != '\n' {
+                // o '\r' de um '\r\n' só fica no espaço dentro de diretivas
+                while self.caractere_atual.is_whitespace()
+                    && self.caractere_atual != '\n'
+                    && (self.em_diretiva
+                        || !(self.caractere_atual == '\r' && self.espiadinha() == '\n'))
+                {
+                    self.avancar();
+                }
+                TipoTrivia::Espaco
+            } else if self.caractere_atual == '/' && self.espiadinha() == '/' {
+                // comentário de linha, sem o '\r' de um '\r\n'
+                while !(matches!(self.caractere_atual, '\n' | '\0')
+                    || (self.caractere_atual == '\r' && self.espiadinha() == '\n'))
+                {
+                    self.avancar();
                 }
+                TipoTrivia::ComentarioLinha
+            } else if self.caractere_atual == '/' && self.espiadinha() == '*' {
+                // comentário de bloco
+                self.avancar(); // avança para o '*'
+                self.avancar(); // avança para o próximo caractere após '*'
+                while !(self.caractere_atual == '*' && self.espiadinha() == '/')
+                    && self.caractere_atual != '\0'
+                {
+                    self.avancar();
+                }
+                if self.caractere_atual == '*' && self.espiadinha() == '/' {
+                    self.avancar(); // avança para o '*'
+                    self.avancar(); // avança para o '/'
+                }
+                TipoTrivia::ComentarioBloco
+            } else {
+                return (self.byte, self.linha, self.coluna);
+            };
+
+            if let Some(trivia) = &mut self.trivia {
+                let (byte, linha, coluna) = inicio;
+                trivia.push(Trivia {
+                    tipo,
+                    texto: &self.fonte[byte..self.fim_anterior],
+                    span: Span::new(byte, self.fim_anterior, linha, coluna)
+                        .no_arquivo(self.arquivo),
+                });
+            }
+        }
+    }
 
-                '"' => {
-                    let texto = self.ler_texto();
-                    Token::Texto(texto)
+    fn ler_token(&mut self, inicio_de_linha: bool) -> Result<Token<'a>, Diagnostic> {
+        // determina o tipo de token com base no caractere atual
+        let token = match self.caractere_atual {
+            '\n' => {
+                self.em_diretiva = false;
+                Token::QuebraLinha
+            }
+            ';' => Token::PontoVirgula,
+            '(' => Token::AbreParentesis,
+            ')' => Token::FechaParentesis,
+            '[' => Token::AbreColchete,
+            ']' => Token::FechaColchete,
+            '{' => Token::AbreChave,
+            '}' => Token::FechaChave,
+            '.' if self.espiadinha().is_ascii_digit() => return Ok(self.ler_numero_ou_erro()),
+            '.' => {
+                if self.espiadinha() == '.' && self.espiar_dois_passos() == '.' {
+                    self.avancar();
+                    self.avancar();
+                    Token::Reticencias
+                } else {
+                    Token::Ponto
                 }
+            }
+            ',' => Token::Virgula,
+            // dígrafo de ']'
+            ':' if self.espiadinha() == '>' => {
+                self.avancar();
+                Token::FechaColchete
+            }
+            ':' => Token::DoisPontos,
+            '?' => Token::Interrogacao,
 
-                '#' => {
-                    let t = self.ler_diretiva_pre_processador();
-                    return (t, linha_token);
+            '\'' => match self.ler_char(Prefixo::Nenhum) {
+                Ok(conteudo_char) => Token::ConteudoChar(conteudo_char, Prefixo::Nenhum),
+                Err(diagnostico) => return Ok(self.literal_invalido(diagnostico)),
+            },
+
+            '"' => match self.ler_texto(Prefixo::Nenhum) {
+                Ok(texto) => Token::Texto(texto, Prefixo::Nenhum),
+                Err(diagnostico) => return Ok(self.literal_invalido(diagnostico)),
+            },
+
+            '#' if inicio_de_linha => {
+                let t = self.ler_diretiva_pre_processador();
+                return Ok(t);
+            }
+            // '%:' é o dígrafo de '#'; a diretiva pula só o ':'
+            '%' if inicio_de_linha && self.espiadinha() == ':' => {
+                self.avancar();
+                return Ok(self.ler_diretiva_pre_processador());
+            }
+
+            '#' => {
+                if self.espiadinha() == '#' {
+                    self.avancar();
+                    Token::CerquilhaDupla
+                } else {
+                    Token::Cerquilha
                 }
+            }
 
-                // operadores e símbolos
-                '=' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::Comparar
-                    } else {
-                        Token::Igual
-                    }
+            // operadores e símbolos
+            '=' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::Comparar
+                } else {
+                    Token::Igual
                 }
+            }
 
-                '+' => {
-                    if self.espiadinha() == '+' {
-                        self.avancar();
-                        Token::Incremento
-                    } else if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::SomaIgual
-                    } else {
-                        Token::Mais
-                    }
+            '+' => {
+                if self.espiadinha() == '+' {
+                    self.avancar();
+                    Token::Incremento
+                } else if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::SomaIgual
+                } else {
+                    Token::Mais
                 }
+            }
 
-                '-' => {
-                    if self.espiadinha() == '-' {
-                        self.avancar();
-                        Token::Decremento
-                    } else if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::SubtracaoIgual
-                    } else {
-                        Token::Menos
-                    }
+            '-' => {
+                if self.espiadinha() == '-' {
+                    self.avancar();
+                    Token::Decremento
+                } else if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::SubtracaoIgual
+                } else if self.espiadinha() == '>' {
+                    self.avancar();
+                    Token::Seta
+                } else {
+                    Token::Menos
                 }
+            }
 
-                '*' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::MultiplicacaoIgual
-                    } else {
-                        Token::Asterisco
-                    }
+            '*' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::MultiplicacaoIgual
+                } else {
+                    Token::Asterisco
                 }
-                '/' => {
-                    if self.espiadinha() == '/' {
-                        // comentário de linha
-                        while self.caractere_atual != '\n' && self.caractere_atual != '\0' {
-                            self.avancar();
-                        }
-                        continue;
-                    } else if self.espiadinha() == '*' {
-                        // comentário de bloco
-                        self.avancar(); // avança para o '*'
-                        self.avancar(); // avança para o próximo caractere após '*'
-                        while !(self.caractere_atual == '*' && self.espiadinha() == '/')
-                            && self.caractere_atual != '\0'
-                        {
-                            self.avancar();
-                        }
-                        if self.caractere_atual == '*' && self.espiadinha() == '/' {
-                            self.avancar(); // avança para o '*'
-                            self.avancar(); // avança para o '/'
-                        }
-                        continue;
-                    } else if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::DivisaoIgual
-                    } else {
-                        Token::Divisao
-                    }
+            }
+            '/' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::DivisaoIgual
+                } else {
+                    Token::Divisao
                 }
+            }
 
-                '%' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::ModuloIgual
-                    } else {
-                        Token::Modulo
-                    }
+            '%' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::ModuloIgual
+                } else if self.espiadinha() == '>' {
+                    self.avancar();
+                    Token::FechaChave
+                } else if self.espiadinha() == ':' && self.espiar(2) == '%' && self.espiar(3) == ':'
+                {
+                    self.avancar();
+                    self.avancar();
+                    self.avancar();
+                    Token::CerquilhaDupla
+                } else if self.espiadinha() == ':' {
+                    self.avancar();
+                    Token::Cerquilha
+                } else {
+                    Token::Modulo
                 }
-                '&' => {
-                    if self.espiadinha() == '&' {
-                        self.avancar();
-                        Token::EComercialDuplo
-                    } else {
-                        Token::EComercial
-                    }
+            }
+            '&' => {
+                if self.espiadinha() == '&' {
+                    self.avancar();
+                    Token::EComercialDuplo
+                } else if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::EComercialIgual
+                } else {
+                    Token::EComercial
                 }
+            }
 
-                '|' => {
-                    if self.espiadinha() == '|' {
-                        self.avancar();
-                        Token::BarraVerticalDupla
-                    } else {
-                        Token::BarraVertical
-                    }
+            '|' => {
+                if self.espiadinha() == '|' {
+                    self.avancar();
+                    Token::BarraVerticalDupla
+                } else if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::BarraVerticalIgual
+                } else {
+                    Token::BarraVertical
                 }
+            }
 
-                '>' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::MaiorOuIgual
-                    } else if self.espiadinha() == '>' {
-                        self.avancar();
-                        Token::DeslocamentoDir
-                    } else {
-                        Token::Maior
-                    }
-                }
-                '<' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::MenorOuIgual
-                    } else if self.espiadinha() == '<' {
-                        self.avancar();
-                        Token::DeslocamentoEsq
-                    } else {
-                        Token::Menor
-                    }
+            '^' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::CircunflexoIgual
+                } else {
+                    Token::Circunflexo
                 }
+            }
 
-                '!' => {
-                    if self.espiadinha() == '=' {
-                        self.avancar();
-                        Token::Diferente
-                    } else {
-                        Token::Negacao
-                    }
+            '~' => Token::Til,
+
+            '>' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::MaiorOuIgual
+                } else if self.espiadinha() == '>' && self.espiar_dois_passos() == '=' {
+                    self.avancar();
+                    self.avancar();
+                    Token::DeslocamentoDirIgual
+                } else if self.espiadinha() == '>' {
+                    self.avancar();
+                    Token::DeslocamentoDir
+                } else {
+                    Token::Maior
+                }
+            }
+            '<' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::MenorOuIgual
+                } else if self.espiadinha() == '<' && self.espiar_dois_passos() == '=' {
+                    self.avancar();
+                    self.avancar();
+                    Token::DeslocamentoEsqIgual
+                } else if self.espiadinha() == '<' {
+                    self.avancar();
+                    Token::DeslocamentoEsq
+                } else if self.espiadinha() == '%' {
+                    self.avancar();
+                    Token::AbreChave
+                } else if self.dialeto == Dialeto::Cpp
+                    && self.espiadinha() == ':'
+                    && self.espiar(2) == ':'
+                    && !matches!(self.espiar(3), ':' | '>')
+                {
+                    // em C++ 'vector<::std::string>' é '<' seguido de '::', não '[:'
+                    Token::Menor
+                } else if self.espiadinha() == ':' {
+                    self.avancar();
+                    Token::AbreColchete
+                } else {
+                    Token::Menor
                 }
+            }
 
-                // [IMPORTANTE] Números consomem até o delimitador, então retornamos ANTES do self.avancar() final
-                '0'..='9' => {
-                    let t = self.ler_numero();
-                    return (t, linha_token);
+            '!' => {
+                if self.espiadinha() == '=' {
+                    self.avancar();
+                    Token::Diferente
+                } else {
+                    Token::Negacao
                 }
+            }
+
+            // [IMPORTANTE] Números consomem até o delimitador, então retornamos ANTES do self.avancar() final
+            '0'..='9' => {
+                return Ok(self.ler_numero_ou_erro());
+            }
 
-                '\0' => Token::Fundo,
+            '\0' => Token::Fundo,
 
-                _ => {
-                    if self.caractere_atual.is_alphabetic() || self.caractere_atual == '_' {
-                        let identificador = self.ler_identificador();
-                        return (Token::Identificador(identificador), linha_token);
+            _ => {
+                if let Some(prefixo) = self.ler_prefixo_literal() {
+                    let literal = if self.caractere_atual == '"' {
+                        self.ler_texto(prefixo)
+                            .map(|texto| Token::Texto(texto, prefixo))
                     } else {
-                        Token::Invalido
+                        self.ler_char(prefixo)
+                            .map(|c| Token::ConteudoChar(c, prefixo))
+                    };
+                    match literal {
+                        Ok(token) => token,
+                        Err(diagnostico) => return Ok(self.literal_invalido(diagnostico)),
                     }
+                } else if self.caractere_atual.is_alphabetic() || self.caractere_atual == '_' {
+                    let identificador = self.ler_identificador();
+                    if let Some(operador) =
+                        Token::operador_alternativo(&identificador, self.dialeto)
+                    {
+                        return Ok(operador);
+                    }
+                    return Ok(match PalavraChave::buscar(&identificador, self.dialeto) {
+                        Some(palavra) => Token::PalavraChave(palavra),
+                        None => Token::Identificador(identificador),
+                    });
+                } else {
+                    Token::Invalido
                 }
-            };
+            }
+        };
+
+        self.avancar();
+
+        Ok(token)
+    }
 
+    // um literal com erro não interrompe o lexer: vira Token::ErroLexico, reportado só
+    // se chegar ao parser, já que numa região desativada por '#if' ele é ignorado.
+    // a aspa final, se houver, é consumida junto
+    fn literal_invalido(&mut self, diagnostico: Diagnostic) -> Token<'a> {
+        if matches!(self.caractere_atual, '\'' | '"') {
             self.avancar();
+        }
+        let texto = self.texto_entre(self.inicio_token, self.fim_anterior);
+        Token::ErroLexico(texto, Box::new(diagnostico))
+    }
 
-            return (token, linha_token);
+    // como ler_numero, mas um número inválido ('09', '0x' antes de um '##') também vira
+    // Token::ErroLexico, com o texto lido
+    fn ler_numero_ou_erro(&mut self) -> Token<'a> {
+        match self.ler_numero() {
+            Ok(token) => token,
+            Err(diagnostico) => self.literal_invalido(diagnostico),
         }
     }
 }
+
+// entrega os tokens até o Token::Fundo, inclusive; números e literais com erro viram
+// Token::ErroLexico, e um erro de prox_token encerraria a iteração em Lexer::erro
+impl<'a> Iterator for Lexer<'a> {
+    type Item = SpannedToken<'a>;
+
+    fn next(&mut self) -> Option<SpannedToken<'a>> {
+        if self.terminado {
+            return None;
+        }
+
+        match self.prox_token() {
+            Ok(token) => {
+                self.terminado = token.token == Token::Fundo;
+                Some(token)
+            }
+            Err(diagnostico) => {
+                self.terminado = true;
+                self.erro = Some(diagnostico);
+                None
+            }
+        }
+    }
+}
+
+// o caractere que o trígrafo '??' seguido do byte representa
+fn trigrafo(b: u8) -> Option<char> {
+    Some(match b {
+        b'=' => '#',
+        b'(' => '[',
+        b'/' => '\\',
+        b')' => ']',
+        b'\'' => '^',
+        b'<' => '{',
+        b'!' => '|',
+        b'>' => '}',
+        b'-' => '~',
+        _ => return None,
+    })
+}
+
+// separa base, dígitos, fração, expoente e sufixo do texto lido por ler_numero
+fn classificar_numero(texto: Cow<'_, str>) -> Result<Token<'_>, String> {
+    // o pp-number só tem caracteres ASCII, então cada byte é um caractere
+    let caractere = |i: usize| {
+        texto
+            .as_bytes()
+            .get(i)
+            .map(|b| b.to_ascii_lowercase() as char)
+    };
+
+    let mut base = match (caractere(0), caractere(1)) {
+        (Some('0'), Some('x')) => Base::Hexadecimal,
+        (Some('0'), Some('b')) => Base::Binaria,
+        _ => Base::Decimal,
+    };
+    let radix_digitos = if base == Base::Hexadecimal { 16 } else { 10 };
+    let digito = |i: usize| caractere(i).is_some_and(|c| c.is_digit(radix_digitos));
+
+    let mut i = if base == Base::Decimal { 0 } else { 2 };
+    let inicio_digitos = i;
+    while digito(i) {
+        i += 1;
+    }
+    let inteiros = &texto[inicio_digitos..i];
+    let mut quantidade = inteiros.len();
+
+    let mut is_float = false;
+    if base != Base::Binaria && caractere(i) == Some('.') {
+        is_float = true;
+        i += 1;
+        let inicio_fracao = i;
+        while digito(i) {
+            i += 1;
+        }
+        quantidade += i - inicio_fracao;
+    }
+
+    if quantidade == 0 {
+        return Err(format!("Literal numérico sem dígitos: '{}'", texto));
+    }
+
+    let letra_expoente = if base == Base::Hexadecimal { 'p' } else { 'e' };
+    let mut tem_expoente = false;
+    if base != Base::Binaria && caractere(i) == Some(letra_expoente) {
+        is_float = true;
+        tem_expoente = true;
+        i += 1;
+        if matches!(caractere(i), Some('+' | '-')) {
+            i += 1;
+        }
+        let inicio_expoente = i;
+        while caractere(i).is_some_and(|c| c.is_ascii_digit()) {
+            i += 1;
+        }
+        if i == inicio_expoente {
+            return Err(format!("Expoente sem dígitos em '{}'", texto));
+        }
+    }
+
+    if base == Base::Hexadecimal && is_float && !tem_expoente {
+        return Err(format!(
+            "Hexadecimal de ponto flutuante precisa do expoente 'p': '{}'",
+            texto
+        ));
+    }
+
+    if !is_float && base == Base::Decimal && inteiros.len() > 1 && inteiros.starts_with('0') {
+        base = Base::Octal;
+    }
+
+    let limite = match base {
+        Base::Binaria => Some('1'),
+        Base::Octal => Some('7'),
+        _ => None,
+    };
+    if let Some(limite) = limite
+        && let Some(invalido) = inteiros.chars().find(|&c| c > limite)
+    {
+        return Err(format!(
+            "Dígito '{}' inválido num literal de base {}: '{}'",
+            invalido,
+            base.radix(),
+            texto
+        ));
+    }
+
+    let sufixo = &texto[i..];
+    let aceito = |validos: &[&str]| validos.iter().any(|v| sufixo.eq_ignore_ascii_case(v));
+    let sufixo_valido = if is_float {
+        aceito(&["", "f", "l"])
+    } else {
+        // 'lL' e 'Ll' não valem, só 'll' e 'LL'
+        let tem_ll = sufixo
+            .as_bytes()
+            .windows(2)
+            .any(|par| par.eq_ignore_ascii_case(b"ll"));
+        aceito(&["", "u", "l", "ul", "lu", "ll", "ull", "llu"])
+            && (!tem_ll || sufixo.contains("ll") || sufixo.contains("LL"))
+    };
+    if !sufixo_valido {
+        return Err(format!(
+            "Sufixo '{}' inválido no literal '{}'",
+            sufixo, texto
+        ));
+    }
+
+    // o sufixo é emprestado do mesmo lugar que o texto
+    let (texto, sufixo) = match texto {
+        Cow::Borrowed(texto) => (Cow::Borrowed(texto), Cow::Borrowed(&texto[i..])),
+        Cow::Owned(texto) => {
+            let sufixo = texto[i..].to_string();
+            (Cow::Owned(texto), Cow::Owned(sufixo))
+        }
+    };
+    let numero = LiteralNumero {
+        texto,
+        base,
+        sufixo,
+    };
+    Ok(if is_float {
+        Token::NumeroFloat(numero)
+    } else {
+        Token::NumeroInt(numero)
+    })
+}
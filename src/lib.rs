@@ -1,8 +1,26 @@
+// o nome do crate vem do projeto original e é usado pelo Tauri
+#![allow(non_snake_case)]
+
+pub mod diagnostico;
+pub mod evaluator;
+pub mod inclusao;
 pub mod lexer;
 pub mod parser;
+pub mod preprocessador;
+pub mod semantico;
+pub mod tipo;
 pub mod token;
 
-// re-exporta para facilitar o uso no Tauri
+// re-exporta para facilitar o uso no Tauri. o dialeto padrão é C: para C++ o Lexer
+// precisa de com_dialeto(Dialeto::Cpp) e o Preprocessador de definir_dialeto
+pub use diagnostico::{Diagnostic, Severidade};
+pub use evaluator::{Valor, avaliar};
+pub use inclusao::{SistemaArquivos, SistemaArquivosMemoria};
 pub use lexer::Lexer;
-pub use parser::{Parser, Stmt};
-pub use token::Token;
+pub use parser::{Expr, ExprKind, Parser, Pragma, Stmt, StmtKind};
+pub use preprocessador::Preprocessador;
+pub use tipo::Tipo;
+pub use token::{
+    Base, Dialeto, LiteralNumero, PalavraChave, Prefixo, Span, SpannedToken, Token, TokenComTrivia,
+    Trivia, reconstruir_fonte,
+};
@@ -1,13 +1,43 @@
-use core::panic;
+use std::collections::HashSet;
 
-use crate::token::Token;
+use crate::diagnostico::{CodigoErro, Diagnostic, Severidade};
+use crate::tipo::{Armazenamento, Definicao, Qualificadores, Sinal, Tipo, TipoBase};
+use crate::token::{Base, LiteralNumero, PalavraChave, Prefixo, Span, SpannedToken, Token};
 use serde::Serialize;
 
+// o nome é opcional em protótipos: 'int soma(int, int);'
 #[derive(Clone, Debug, Serialize)]
-pub struct Parametro {
-    pub tipo: Vec<Token>,
-    pub nome: Token,
-    pub tamanho_array: Option<Expr>,
+pub struct Parametro<'a> {
+    pub tipo: Tipo<'a>,
+    pub nome: Option<Token<'a>>,
+    pub span: Span,
+}
+
+// membro de uma struct ou union; bits é a largura de um campo de bits. o nome falta
+// num membro anônimo ('union { int i; float f; };') e num campo de bits de
+// alinhamento ('int : 0;')
+#[derive(Clone, Debug, Serialize)]
+pub struct Campo<'a> {
+    pub tipo: Tipo<'a>,
+    pub nome: Option<Token<'a>>,
+    pub bits: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+// um dos nomes de 'int a = 1, *b, c[3];', já com o tipo completo
+#[derive(Clone, Debug, Serialize)]
+pub struct Declarador<'a> {
+    pub tipo: Tipo<'a>,
+    pub nome: Token<'a>,
+    pub inicializador: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Enumerador<'a> {
+    pub nome: String,
+    pub valor: Option<Expr<'a>>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -27,200 +57,685 @@ pub enum Operador {
     EComercialDuplo,
     BarraVertical,
     BarraVerticalDupla,
+    Xor,
     DeslocamentoEsq,
     DeslocamentoDir,
 }
 
 #[derive(Clone, Debug, Serialize)]
-pub enum Expr {
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub span: Span,
+}
+
+impl<'a> Expr<'a> {
+    pub fn new(kind: ExprKind<'a>, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub enum ExprKind<'a> {
     NumeroInt(i64),
+    // constante com 'u' ou grande demais para i64 ('0xFFFFFFFFFFFFFFFF')
+    NumeroUnsigned(u64),
     NumeroFloat(f64),
     Binario {
-        esquerda: Box<Expr>,
+        esquerda: Box<Expr<'a>>,
         operador: Operador,
-        direita: Box<Expr>,
+        direita: Box<Expr<'a>>,
     },
-    Agrupamento(Box<Expr>),
-    Variavel(Token),
+    Agrupamento(Box<Expr<'a>>),
+    Variavel(Token<'a>),
     Atribuicao {
-        alvo: Box<Expr>,
-        valor: Box<Expr>,
+        alvo: Box<Expr<'a>>,
+        valor: Box<Expr<'a>>,
     },
     AtribuicaoComposta {
-        alvo: Box<Expr>,
-        operador: Token,
-        valor: Box<Expr>,
+        alvo: Box<Expr<'a>>,
+        operador: Token<'a>,
+        valor: Box<Expr<'a>>,
     },
     Unario {
-        operador: Token,
-        direita: Box<Expr>,
+        operador: Token<'a>,
+        direita: Box<Expr<'a>>,
     },
     Posfixa {
-        expressao: Box<Expr>,
-        operador: Token,
+        expressao: Box<Expr<'a>>,
+        operador: Token<'a>,
     },
     AcessoArray {
-        nome: Box<Expr>,
-        indice: Box<Expr>,
+        nome: Box<Expr<'a>>,
+        indice: Box<Expr<'a>>,
+    },
+    // objeto.membro (operador Ponto) ou ponteiro->membro (operador Seta)
+    AcessoMembro {
+        objeto: Box<Expr<'a>>,
+        operador: Token<'a>,
+        membro: String,
     },
     ChamadaFuncao {
-        callee: Box<Expr>,
-        argumentos: Vec<Expr>,
+        callee: Box<Expr<'a>>,
+        argumentos: Vec<Expr<'a>>,
+    },
+    CharLiteral(char, Prefixo),
+    // 'true' e 'false' do C++
+    BoolLiteral(bool),
+    StringLiteral(String, Prefixo),
+    // '{ 1, 2, .x = 3 }', só aparece como inicializador
+    ListaInicializacao(Vec<ItemInicializacao<'a>>),
+    Ternario {
+        condicao: Box<Expr<'a>>,
+        entao: Box<Expr<'a>>,
+        senao: Box<Expr<'a>>,
+    },
+    // operador vírgula: avalia a esquerda, descarta o valor e resulta na direita
+    Virgula {
+        esquerda: Box<Expr<'a>>,
+        direita: Box<Expr<'a>>,
+    },
+    Sizeof(OperandoSizeof<'a>),
+    Cast {
+        tipo: Tipo<'a>,
+        expressao: Box<Expr<'a>>,
+    },
+}
+
+// 'sizeof(int *)' mede um tipo; 'sizeof x' e 'sizeof(x)' medem uma expressão
+#[derive(Clone, Debug, Serialize)]
+pub enum OperandoSizeof<'a> {
+    Tipo(Tipo<'a>),
+    Expressao(Box<Expr<'a>>),
+}
+
+// '.membro' ou '[indice]' antes do '=' num inicializador designado (C99)
+#[derive(Clone, Debug, Serialize)]
+pub enum Designador<'a> {
+    Membro(String),
+    Indice(Expr<'a>),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ItemInicializacao<'a> {
+    pub designadores: Vec<Designador<'a>>,
+    pub valor: Expr<'a>,
+    pub span: Span,
+}
+
+// um 'case' (ou o 'default', quando valor é None) com as declarações que o seguem;
+// sem 'break' no fim a execução continua no próximo caso
+#[derive(Debug, Serialize)]
+pub struct CasoSwitch<'a> {
+    pub valor: Option<Expr<'a>>,
+    pub declaracoes: Vec<Stmt<'a>>,
+    pub span: Span,
+}
+
+// '#pragma' já separado nas formas que os outros passos entendem
+#[derive(Clone, Debug, Serialize)]
+pub enum Pragma<'a> {
+    Once,
+    // 'pack(n)', 'pack()', 'pack(push)', 'pack(push, n)' e 'pack(pop)'
+    Pack {
+        operacao: OperacaoPack,
+        alinhamento: Option<u64>,
+    },
+    Outro {
+        nome: String,
+        argumentos: Vec<Token<'a>>,
     },
-    CharLiteral(char),
-    StringLiteral(String),
-    ArrayDim,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum OperacaoPack {
+    Definir,
+    Empilhar,
+    Desempilhar,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stmt<'a> {
+    pub kind: StmtKind<'a>,
+    pub span: Span,
+}
+
+impl<'a> Stmt<'a> {
+    pub fn new(kind: StmtKind<'a>, span: Span) -> Self {
+        Self { kind, span }
+    }
 }
 
 #[derive(Debug, Serialize)]
-pub enum Stmt {
-    Expressao(Expr),
-    Retorno(Option<Expr>),
+pub enum StmtKind<'a> {
+    Expressao(Expr<'a>),
+    Retorno(Option<Expr<'a>>),
+    // também representa protótipos ('int f(int);'), cujo tipo é Tipo::Funcao
     DeclaracaoVariavel {
-        tipo: Vec<Token>,
-        nome: Token,
-        tamanho_array: Option<Expr>,
-        inicializador: Option<Expr>,
+        armazenamento: Option<Armazenamento>,
+        declaradores: Vec<Declarador<'a>>,
     },
     Inclusao {
         path: String,
         is_global: bool,
     },
     Diretiva(String),
+    Pragma(Pragma<'a>),
     DeclaracaoFuncao {
-        tipo_retorno: Vec<Token>,
-        nome: Token,
-        parametros: Vec<Parametro>,
-        corpo: Box<Stmt>,
+        armazenamento: Option<Armazenamento>,
+        tipo_retorno: Tipo<'a>,
+        nome: Token<'a>,
+        parametros: Vec<Parametro<'a>>,
+        variadica: bool,
+        corpo: Box<Stmt<'a>>,
     },
     If {
-        condicao: Expr,
-        bloco_then: Box<Stmt>,
-        bloco_else: Option<Box<Stmt>>,
+        condicao: Expr<'a>,
+        bloco_then: Box<Stmt<'a>>,
+        bloco_else: Option<Box<Stmt<'a>>>,
     },
     Bloco {
-        declaracoes: Vec<Stmt>,
+        declaracoes: Vec<Stmt<'a>>,
     },
     Using {
         namespace: String,
     },
+    While {
+        condicao: Expr<'a>,
+        corpo: Box<Stmt<'a>>,
+    },
+    DoWhile {
+        corpo: Box<Stmt<'a>>,
+        condicao: Expr<'a>,
+    },
+    For {
+        inicializacao: Option<Box<Stmt<'a>>>,
+        condicao: Option<Expr<'a>>,
+        incremento: Option<Expr<'a>>,
+        corpo: Box<Stmt<'a>>,
+    },
+    Switch {
+        expressao: Expr<'a>,
+        casos: Vec<CasoSwitch<'a>>,
+    },
+    Break,
+    Continue,
+    Goto(String),
+    Rotulo {
+        nome: String,
+        declaracao: Box<Stmt<'a>>,
+    },
+    // campos é None numa declaração antecipada ('struct Nome;'); declaracao guarda as
+    // variáveis declaradas junto com a definição ('struct Nome { ... } p;')
+    Struct {
+        nome: Option<String>,
+        campos: Option<Vec<Campo<'a>>>,
+        declaracao: Option<Box<Stmt<'a>>>,
+    },
+    Union {
+        nome: Option<String>,
+        campos: Option<Vec<Campo<'a>>>,
+        declaracao: Option<Box<Stmt<'a>>>,
+    },
+    Enum {
+        nome: Option<String>,
+        enumeradores: Option<Vec<Enumerador<'a>>>,
+        declaracao: Option<Box<Stmt<'a>>>,
+    },
+    // ';' sozinho, como em 'while (*p++) ;' ou 'rotulo: ;'
+    Vazia,
+    // marca o lugar de uma declaração que não pôde ser analisada
+    Erro,
+}
+
+// palavras que iniciam declarações e servem de ponto de sincronização após um erro
+fn inicia_tipo(palavra: PalavraChave) -> bool {
+    use PalavraChave::*;
+
+    matches!(
+        palavra,
+        Void | Char
+            | Short
+            | Int
+            | Long
+            | Float
+            | Double
+            | Signed
+            | Unsigned
+            | Bool
+            | _Bool
+            | Struct
+            | Union
+            | Enum
+            | Typedef
+            | Const
+            | Volatile
+            | Static
+            | Extern
+            | Auto
+            | Register
+            | Inline
+            | Restrict
+    )
+}
+
+// partes de 'long unsigned int const' acumuladas antes de decidir o tipo base
+#[derive(Default)]
+struct Especificadores<'a> {
+    armazenamento: Option<Armazenamento>,
+    qualificadores: Qualificadores,
+    sinal: Option<Sinal>,
+    base: Option<TipoBase>,
+    shorts: usize,
+    longs: usize,
+    is_int: bool,
+    definicao: Option<Definicao<'a>>,
 }
 
-pub struct Parser {
-    tokens: Vec<(Token, usize)>,
+impl Especificadores<'_> {
+    fn sem_tipo(&self) -> bool {
+        self.base.is_none()
+            && self.sinal.is_none()
+            && self.shorts == 0
+            && self.longs == 0
+            && !self.is_int
+    }
+}
+
+// um passo do declarador, na ordem em que é aplicado sobre o tipo base
+enum Derivacao<'a> {
+    Ponteiro(Qualificadores),
+    Array(Option<Expr<'a>>),
+    Funcao(Vec<Parametro<'a>>, bool),
+}
+
+pub struct Parser<'a> {
+    tokens: Vec<SpannedToken<'a>>,
     posicao_atual: usize,
-    token_atual: Token,
-    linha_atual: usize, 
+    token_atual: Token<'a>,
+    span_atual: Span,
+    span_anterior: Span,
+    profundidade_bloco: usize,
+    diagnosticos: Vec<Diagnostic>,
+    // nomes introduzidos por typedef, para separar declarações de expressões
+    typedefs: HashSet<String>,
+    // nomes de variáveis, funções e parâmetros já declarados; nunca são tomados por tipo
+    variaveis: HashSet<String>,
+    // nome da função cujo corpo está sendo lido, para '__func__'
+    funcao_atual: Option<String>,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<(Token, usize)>) -> Self {
-        let (token_atual, linha_atual) = if tokens.is_empty() {
-            (Token::Fundo, 0)
-        } else {
-            tokens[0].clone()
+impl<'a> Parser<'a> {
+    pub fn new(tokens: Vec<SpannedToken<'a>>) -> Self {
+        let (token_atual, span_atual) = match tokens.first() {
+            Some(primeiro) => (primeiro.token.clone(), primeiro.span),
+            None => (Token::Fundo, Span::default()),
         };
 
         Self {
             tokens,
             posicao_atual: 0,
             token_atual,
-            linha_atual,
+            span_atual,
+            span_anterior: span_atual,
+            profundidade_bloco: 0,
+            diagnosticos: Vec::new(),
+            typedefs: HashSet::new(),
+            variaveis: HashSet::new(),
+            funcao_atual: None,
         }
     }
 
     pub fn avancar(&mut self) {
+        self.span_anterior = self.span_atual;
+
         if self.posicao_atual + 1 < self.tokens.len() {
             self.posicao_atual += 1;
-            let (tok, lin) = self.tokens[self.posicao_atual].clone();
-            self.token_atual = tok;
-            self.linha_atual = lin;
+            let SpannedToken { token, span } = self.tokens[self.posicao_atual].clone();
+            self.token_atual = token;
+            self.span_atual = span;
         } else {
             self.token_atual = Token::Fundo;
         }
     }
 
-    fn espiadinha(&self) -> Token {
-        if self.posicao_atual + 1 < self.tokens.len() {
-            self.tokens[self.posicao_atual + 1].0.clone()
+    fn espiadinha(&self) -> Token<'a> {
+        self.espiar(1)
+    }
+
+    fn espiar(&self, passos: usize) -> Token<'a> {
+        if self.posicao_atual + passos < self.tokens.len() {
+            self.tokens[self.posicao_atual + passos].token.clone()
         } else {
             Token::Fundo
         }
     }
 
-    fn espiar_dois_passos(&self) -> Token {
-        if self.posicao_atual + 2 < self.tokens.len() {
-            self.tokens[self.posicao_atual + 2].0.clone()
+    // span que vai do início dado até o fim do último token consumido
+    fn span_desde(&self, inicio: Span) -> Span {
+        inicio.ate(self.span_anterior)
+    }
+
+    fn erro(&self, codigo: CodigoErro, mensagem: String) -> Diagnostic {
+        Diagnostic::erro(codigo, mensagem, self.span_atual)
+    }
+
+    // literais de texto vizinhos viram um só ("a" "b" é "ab"); o prefixo de um vale
+    // para todos, mas prefixos diferentes não se misturam
+    fn parse_literal_texto(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        let mut valor = String::new();
+        let mut prefixo = Prefixo::Nenhum;
+
+        while let Token::Texto(parte, prefixo_parte) = &self.token_atual {
+            if *prefixo_parte != Prefixo::Nenhum {
+                if prefixo != Prefixo::Nenhum && prefixo != *prefixo_parte {
+                    let mensagem = format!(
+                        "Literais com prefixos '{}' e '{}' não podem ser concatenados",
+                        prefixo.como_str(),
+                        prefixo_parte.como_str()
+                    );
+                    self.diagnosticos
+                        .push(self.erro(CodigoErro::TokenInesperado, mensagem));
+                } else {
+                    prefixo = *prefixo_parte;
+                }
+            }
+
+            valor.push_str(parte);
+            self.avancar();
+        }
+
+        Ok(Expr::new(
+            ExprKind::StringLiteral(valor, prefixo),
+            self.span_desde(inicio),
+        ))
+    }
+
+    // constantes com 'u' ou acima de i64::MAX viram NumeroUnsigned; as que não cabem
+    // em 64 bits viram diagnóstico
+    fn constante_inteira(&mut self, numero: &LiteralNumero) -> ExprKind<'a> {
+        let Some(valor) = numero.valor_inteiro() else {
+            self.diagnosticos.push(self.erro(
+                CodigoErro::ConstanteForaDoIntervalo,
+                format!("Constante inteira '{}' não cabe em 64 bits", numero.texto),
+            ));
+            return ExprKind::NumeroInt(0);
+        };
+
+        // só decimais sem 'u' deveriam caber em long long; hexadecimais e octais
+        // podem ser unsigned long long
+        if valor > i64::MAX as u64
+            && numero.base == Base::Decimal
+            && !numero.sufixo.contains(['u', 'U'])
+        {
+            self.diagnosticos.push(Diagnostic::aviso(
+                CodigoErro::ConstanteForaDoIntervalo,
+                format!(
+                    "Constante inteira '{}' grande demais para long long; tratada como unsigned",
+                    numero.texto
+                ),
+                self.span_atual,
+            ));
+        }
+
+        if valor > i64::MAX as u64 || numero.sufixo.contains(['u', 'U']) {
+            ExprKind::NumeroUnsigned(valor)
         } else {
-            Token::Fundo
+            ExprKind::NumeroInt(valor as i64)
+        }
+    }
+
+    // consome o token esperado ou devolve o erro com a mensagem dada
+    fn esperar(
+        &mut self,
+        esperado: Token<'a>,
+        codigo: CodigoErro,
+        mensagem: &str,
+    ) -> Result<(), Diagnostic> {
+        if self.token_atual != esperado {
+            return Err(self.erro(
+                codigo,
+                format!("{}, mas foi recebido {:?}", mensagem, self.token_atual),
+            ));
+        }
+        self.avancar();
+        Ok(())
+    }
+
+    // modo pânico: descarta tokens até um ponto seguro para recomeçar a análise
+    fn sincronizar(&mut self) {
+        let mut chaves_abertas = 0;
+
+        while self.token_atual != Token::Fundo {
+            match &self.token_atual {
+                Token::AbreChave => chaves_abertas += 1,
+
+                Token::FechaChave if chaves_abertas > 0 => {
+                    chaves_abertas -= 1;
+                    if chaves_abertas == 0 {
+                        self.avancar();
+                        return;
+                    }
+                }
+
+                // o '}' pertence ao bloco que está sendo analisado
+                Token::FechaChave if self.profundidade_bloco > 0 => return,
+
+                Token::FechaChave => {
+                    self.avancar();
+                    return;
+                }
+
+                Token::PontoVirgula if chaves_abertas == 0 => {
+                    self.avancar();
+                    return;
+                }
+
+                Token::PalavraChave(palavra) if chaves_abertas == 0 && inicia_tipo(*palavra) => {
+                    return;
+                }
+
+                _ => {}
+            }
+            self.avancar();
         }
     }
 
-    fn erro(&self, mensagem: String) -> ! {
-        panic!("Erro na linha {}: {}", self.linha_atual, mensagem);
+    // analisa uma declaração; em caso de erro registra o diagnóstico e devolve StmtKind::Erro
+    fn parse_declaracao_recuperando(&mut self) -> Stmt<'a> {
+        let posicao_inicial = self.posicao_atual;
+        let inicio = self.span_atual;
+
+        match self.parse_declaracao() {
+            Ok(declaracao) => declaracao,
+            Err(diagnostico) => {
+                self.diagnosticos.push(diagnostico);
+                self.sincronizar();
+
+                // garante progresso quando o erro acontece no próprio ponto de sincronização
+                if self.posicao_atual == posicao_inicial && self.token_atual != Token::Fundo {
+                    self.avancar();
+                }
+                Stmt::new(StmtKind::Erro, self.span_desde(inicio))
+            }
+        }
     }
 
-    pub fn parse_primario(&mut self) -> Expr {
+    // operadores prefixos, 'sizeof' e conversões; o operando é outra expressão unária,
+    // então '*p++' vira '*(p++)' e '-a[i]' vira '-(a[i])'
+    pub fn parse_unario(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+
         match &self.token_atual {
-            Token::Menos
+            Token::Mais
+            | Token::Menos
             | Token::Decremento
             | Token::Incremento
             | Token::Negacao
+            | Token::Til
             | Token::EComercial
             | Token::Asterisco => {
                 let operador = self.token_atual.clone();
                 self.avancar();
-                let direita = self.parse_primario();
-                return Expr::Unario {
-                    operador,
-                    direita: Box::new(direita),
-                };
+                let direita = self.parse_unario()?;
+                Ok(Expr::new(
+                    ExprKind::Unario {
+                        operador,
+                        direita: Box::new(direita),
+                    },
+                    self.span_desde(inicio),
+                ))
             }
 
-            Token::AbreParentesis => {
+            Token::PalavraChave(PalavraChave::Sizeof) => {
                 self.avancar();
-                let expr = self.parse_atribuicao();
-                if let Token::FechaParentesis = self.token_atual {
+
+                let operando = if self.token_atual == Token::AbreParentesis
+                    && self.e_nome_tipo(&self.espiadinha())
+                {
                     self.avancar();
+                    let tipo = self.parse_nome_tipo()?;
+                    self.esperar(
+                        Token::FechaParentesis,
+                        CodigoErro::ParentesisEsperado,
+                        "Esperado ')' após o tipo em 'sizeof'",
+                    )?;
+                    OperandoSizeof::Tipo(tipo)
                 } else {
-                    self.erro(format!(
-                        "Esperado ')', mas foi recebido {:?}",
-                        self.token_atual
+                    OperandoSizeof::Expressao(Box::new(self.parse_unario()?))
+                };
+
+                Ok(Expr::new(
+                    ExprKind::Sizeof(operando),
+                    self.span_desde(inicio),
+                ))
+            }
+
+            // '(int)x': um tipo entre parênteses antes do operando é uma conversão
+            Token::AbreParentesis if self.e_nome_tipo(&self.espiadinha()) => {
+                self.avancar();
+                let tipo = self.parse_nome_tipo()?;
+                self.esperar(
+                    Token::FechaParentesis,
+                    CodigoErro::ParentesisEsperado,
+                    "Esperado ')' após o tipo da conversão",
+                )?;
+                let expressao = self.parse_unario()?;
+
+                Ok(Expr::new(
+                    ExprKind::Cast {
+                        tipo,
+                        expressao: Box::new(expressao),
+                    },
+                    self.span_desde(inicio),
+                ))
+            }
+
+            _ => self.parse_fator(),
+        }
+    }
+
+    pub fn parse_primario(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+
+        if self.token_atual == Token::AbreParentesis {
+            self.avancar();
+            let expr = self.parse_virgula()?;
+            if let Token::FechaParentesis = self.token_atual {
+                self.avancar();
+            } else {
+                return Err(self.erro(
+                    CodigoErro::ParentesisEsperado,
+                    format!("Esperado ')', mas foi recebido {:?}", self.token_atual),
+                ));
+            }
+            return Ok(Expr::new(
+                ExprKind::Agrupamento(Box::new(expr)),
+                self.span_desde(inicio),
+            ));
+        }
+
+        let kind = match &self.token_atual {
+            Token::NumeroInt(numero) => {
+                let numero = numero.clone();
+                self.constante_inteira(&numero)
+            }
+
+            Token::NumeroFloat(numero) => {
+                let valor = numero.valor_float();
+                if valor.is_infinite() {
+                    self.diagnosticos.push(Diagnostic::aviso(
+                        CodigoErro::ConstanteForaDoIntervalo,
+                        format!("Constante '{}' fora do intervalo de double", numero.texto),
+                        self.span_atual,
                     ));
                 }
-                return Expr::Agrupamento(Box::new(expr));
+                ExprKind::NumeroFloat(valor)
             }
 
-            _ => {}
-        }
+            Token::ConteudoChar(valor_char, prefixo) => {
+                ExprKind::CharLiteral(*valor_char, *prefixo)
+            }
+
+            Token::Texto(..) => return self.parse_literal_texto(),
+
+            // o erro do lexer só aparece aqui, já fora das regiões desativadas
+            Token::ErroLexico(_, diagnostico) => return Err((**diagnostico).clone()),
+
+            // dentro de uma função, '__func__' é o nome dela (C99 6.4.2.2)
+            Token::Identificador(nome) if nome == "__func__" && self.funcao_atual.is_some() => {
+                ExprKind::StringLiteral(
+                    self.funcao_atual.clone().unwrap_or_default(),
+                    Prefixo::Nenhum,
+                )
+            }
+
+            Token::PalavraChave(PalavraChave::True) => ExprKind::BoolLiteral(true),
+            Token::PalavraChave(PalavraChave::False) => ExprKind::BoolLiteral(false),
 
-        let expr = match &self.token_atual {
-            Token::NumeroInt(valor_string) => {
-                let valor = valor_string.parse::<i64>().unwrap();
-                Expr::NumeroInt(valor)
+            Token::Identificador(_)
+            | Token::PalavraChave(PalavraChave::This | PalavraChave::Nullptr) => {
+                ExprKind::Variavel(self.token_atual.clone())
             }
 
-            Token::NumeroFloat(valor_string) => {
-                let valor = valor_string.parse::<f64>().unwrap();
-                Expr::NumeroFloat(valor)
+            _ => {
+                return Err(self.erro(
+                    CodigoErro::PrimarioEsperado,
+                    format!("Esperado primário, recebido {:?}", self.token_atual),
+                ));
             }
+        };
 
-            Token::ConteudoChar(valor_char) => Expr::CharLiteral(*valor_char),
+        self.avancar();
+        Ok(Expr::new(kind, self.span_desde(inicio)))
+    }
 
-            Token::Texto(valor_string) => Expr::StringLiteral(valor_string.to_string()),
+    pub fn parse_fator(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_primario()?;
 
-            Token::Identificador(_) => {
-                if self.espiadinha() == Token::AbreParentesis {
-                    let nome = self.token_atual.clone();
+        loop {
+            match &self.token_atual {
+                Token::Incremento | Token::Decremento => {
+                    let operador_posfixo = self.token_atual.clone();
                     self.avancar();
+                    let span = self.span_desde(expr.span);
+                    expr = Expr::new(
+                        ExprKind::Posfixa {
+                            expressao: Box::new(expr),
+                            operador: operador_posfixo,
+                        },
+                        span,
+                    );
+                }
 
+                // qualquer expressão pode ser chamada: '(*fp)(x)', 'tbl[i](x)', 'f(a)(b)'
+                Token::AbreParentesis => {
                     self.avancar();
 
                     let mut argumentos = Vec::new();
-
                     if self.token_atual != Token::FechaParentesis {
                         loop {
-                            argumentos.push(self.parse_atribuicao());
+                            argumentos.push(self.parse_atribuicao()?);
                             if self.token_atual == Token::Virgula {
                                 self.avancar();
                             } else {
@@ -230,69 +745,98 @@ impl Parser {
                     }
 
                     if self.token_atual != Token::FechaParentesis {
-                        self.erro(format!(
-                            "esperado ')' após argumentos, recebido {:?}",
-                            self.token_atual
+                        return Err(self.erro(
+                            CodigoErro::ParentesisEsperado,
+                            format!(
+                                "esperado ')' após argumentos, recebido {:?}",
+                                self.token_atual
+                            ),
                         ));
                     }
-
-                    Expr::ChamadaFuncao {
-                        callee: Box::new(Expr::Variavel(nome)),
-                        argumentos,
-                    }
-                } else {
-                    Expr::Variavel(self.token_atual.clone())
-                }
-            }
-
-            _ => self.erro(format!(
-                "Esperado primário, recebido {:?}",
-                self.token_atual
-            )),
-        };
-
-        self.avancar();
-        expr
-    }
-
-    pub fn parse_fator(&mut self) -> Expr {
-        let mut expr = self.parse_primario();
-
-        loop {
-            match &self.token_atual {
-                Token::Incremento | Token::Decremento => {
-                    let operador_posfixo = self.token_atual.clone();
                     self.avancar();
-                    expr = Expr::Posfixa {
-                        expressao: Box::new(expr),
-                        operador: operador_posfixo,
-                    };
+
+                    let span = self.span_desde(expr.span);
+                    expr = Expr::new(
+                        ExprKind::ChamadaFuncao {
+                            callee: Box::new(expr),
+                            argumentos,
+                        },
+                        span,
+                    );
                 }
 
                 Token::AbreColchete => {
                     self.avancar();
-                    let indice = self.parse_atribuicao();
+                    let indice = self.parse_virgula()?;
                     if self.token_atual != Token::FechaColchete {
-                        self.erro(format!(
-                            "Esperando ']' após o índice do array, mas foi recebido {:?}",
-                            self.token_atual
+                        return Err(self.erro(
+                            CodigoErro::ColcheteEsperado,
+                            format!(
+                                "Esperando ']' após o índice do array, mas foi recebido {:?}",
+                                self.token_atual
+                            ),
                         ));
                     }
                     self.avancar();
-                    expr = Expr::AcessoArray {
-                        nome: Box::new(expr),
-                        indice: Box::new(indice),
-                    }
+                    let span = self.span_desde(expr.span);
+                    expr = Expr::new(
+                        ExprKind::AcessoArray {
+                            nome: Box::new(expr),
+                            indice: Box::new(indice),
+                        },
+                        span,
+                    );
+                }
+
+                Token::Ponto | Token::Seta => {
+                    let operador = self.token_atual.clone();
+                    self.avancar();
+
+                    let membro = if let Token::Identificador(membro) = &self.token_atual {
+                        membro.clone()
+                    } else {
+                        return Err(self.erro(
+                            CodigoErro::NomeEsperado,
+                            format!(
+                                "Esperado nome do membro após {:?}, mas foi recebido {:?}",
+                                operador, self.token_atual
+                            ),
+                        ));
+                    };
+                    self.avancar();
+
+                    let span = self.span_desde(expr.span);
+                    expr = Expr::new(
+                        ExprKind::AcessoMembro {
+                            objeto: Box::new(expr),
+                            operador,
+                            membro: membro.into_owned(),
+                        },
+                        span,
+                    );
                 }
 
                 _ => break,
             }
         }
-        expr
+        Ok(expr)
+    }
+
+    // monta um nó binário cobrindo os dois operandos
+    fn binario(esquerda: Expr<'a>, operador: Operador, direita: Expr<'a>) -> Expr<'a> {
+        let span = esquerda.span.ate(direita.span);
+        Expr::new(
+            ExprKind::Binario {
+                esquerda: Box::new(esquerda),
+                operador,
+                direita: Box::new(direita),
+            },
+            span,
+        )
     }
 
-    pub fn parse_termo(&mut self) -> Expr {
-        let mut expr = self.parse_fator();
+    pub fn parse_termo(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_unario()?;
 
         while let Token::Asterisco | Token::Divisao | Token::Modulo = &self.token_atual {
             let operador = match &self.token_atual {
@@ -302,18 +846,14 @@ impl Parser {
                 _ => unreachable!(),
             };
             self.avancar();
-            let direita = self.parse_fator();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_unario()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_expressao(&mut self) -> Expr {
-        let mut expr = self.parse_termo();
+    pub fn parse_expressao(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_termo()?;
 
         while let Token::Mais | Token::Menos = &self.token_atual {
             let operador = match &self.token_atual {
@@ -322,18 +862,14 @@ impl Parser {
                 _ => unreachable!(),
             };
             self.avancar();
-            let direita = self.parse_termo();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_termo()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_bitwise_and(&mut self) -> Expr {
-        let mut expr = self.parse_comparacao();
+    pub fn parse_bitwise_and(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_igualdade()?;
 
         while let Token::EComercial = &self.token_atual {
             let operador = match &self.token_atual {
@@ -341,96 +877,99 @@ impl Parser {
                 _ => unreachable!(),
             };
             self.avancar();
-            let direita = self.parse_comparacao();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_igualdade()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_bitwise_or(&mut self) -> Expr {
-        let mut expr = self.parse_bitwise_and();
+    pub fn parse_bitwise_xor(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_bitwise_and()?;
+
+        while let Token::Circunflexo = &self.token_atual {
+            let operador = Operador::Xor;
+            self.avancar();
+            let direita = self.parse_bitwise_and()?;
+            expr = Self::binario(expr, operador, direita);
+        }
+        Ok(expr)
+    }
+
+    pub fn parse_bitwise_or(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_bitwise_xor()?;
 
         while let Token::BarraVertical = &self.token_atual {
             let operador = Operador::BarraVertical;
             self.avancar();
-            let direita = self.parse_bitwise_and();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_bitwise_xor()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_logical_and(&mut self) -> Expr {
-        let mut expr = self.parse_bitwise_or();
+    pub fn parse_logical_and(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_bitwise_or()?;
 
         while let Token::EComercialDuplo = &self.token_atual {
             let operador = Operador::EComercialDuplo;
             self.avancar();
-            let direita = self.parse_bitwise_or();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_bitwise_or()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_logical_or(&mut self) -> Expr {
-        let mut expr = self.parse_logical_and();
+    pub fn parse_logical_or(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_logical_and()?;
 
         while let Token::BarraVerticalDupla = &self.token_atual {
             let operador = Operador::BarraVerticalDupla;
             self.avancar();
-            let direita = self.parse_logical_and();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
+            let direita = self.parse_logical_and()?;
+            expr = Self::binario(expr, operador, direita);
+        }
+        Ok(expr)
+    }
+
+    // '==' e '!=' ficam um nível abaixo de '<' e '>': 'a == b < c' é 'a == (b < c)'
+    pub fn parse_igualdade(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_relacional()?;
+
+        while let Token::Comparar | Token::Diferente = &self.token_atual {
+            let operador = match &self.token_atual {
+                Token::Comparar => Operador::Comparar,
+                Token::Diferente => Operador::Diferente,
+                _ => unreachable!(),
             };
+            self.avancar();
+            let direita = self.parse_relacional()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_comparacao(&mut self) -> Expr {
-        let mut expr = self.parse_shift();
+    pub fn parse_relacional(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_shift()?;
 
-        while let Token::Maior
-        | Token::Menor
-        | Token::MaiorOuIgual
-        | Token::MenorOuIgual
-        | Token::Comparar
-        | Token::Diferente = &self.token_atual
+        while let Token::Maior | Token::Menor | Token::MaiorOuIgual | Token::MenorOuIgual =
+            &self.token_atual
         {
             let operador = match &self.token_atual {
                 Token::Maior => Operador::Maior,
                 Token::Menor => Operador::Menor,
                 Token::MaiorOuIgual => Operador::MaiorOuIgual,
                 Token::MenorOuIgual => Operador::MenorOuIgual,
-                Token::Comparar => Operador::Comparar,
-                Token::Diferente => Operador::Diferente,
                 _ => unreachable!(),
             };
             self.avancar();
-            let direita = self.parse_shift();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_shift()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_shift(&mut self) -> Expr {
-        let mut expr = self.parse_expressao();
+    pub fn parse_shift(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_expressao()?;
 
         while let Token::DeslocamentoEsq | Token::DeslocamentoDir = &self.token_atual {
             let operador = match &self.token_atual {
@@ -439,57 +978,156 @@ impl Parser {
                 _ => unreachable!(),
             };
             self.avancar();
-            let direita = self.parse_expressao();
-            expr = Expr::Binario {
-                esquerda: Box::new(expr),
-                operador,
-                direita: Box::new(direita),
-            };
+            let direita = self.parse_expressao()?;
+            expr = Self::binario(expr, operador, direita);
         }
-        expr
+        Ok(expr)
     }
 
-    pub fn parse_atribuicao(&mut self) -> Expr {
-        let expr_esquerda = self.parse_logical_or();
+    // 'a ? b : c' associa à direita; o meio aceita qualquer expressão, inclusive vírgula
+    pub fn parse_ternario(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let condicao = self.parse_logical_or()?;
 
-        if self.token_atual == Token::Igual {
-            self.avancar();
-            let valor = self.parse_atribuicao();
+        if self.token_atual != Token::Interrogacao {
+            return Ok(condicao);
+        }
+        self.avancar();
 
-            match expr_esquerda {
-                Expr::Variavel(_) | Expr::AcessoArray { .. } | Expr::Unario { .. } => {
-                    return Expr::Atribuicao {
-                        alvo: Box::new(expr_esquerda),
-                        valor: Box::new(valor),
-                    };
-                }
-                _ => self.erro(format!("Erro de Sintaxe: Alvo inválido para atribuição. A expressão da esquerda é: {:?}", expr_esquerda)),
-            }
-        } else if let Token::SomaIgual
-        | Token::SubtracaoIgual
-        | Token::MultiplicacaoIgual
-        | Token::DivisaoIgual
-        | Token::ModuloIgual = &self.token_atual
-        {
-            let operador = self.token_atual.clone();
-            self.avancar();
-            let valor = self.parse_atribuicao();
+        let entao = self.parse_virgula()?;
+        self.esperar(
+            Token::DoisPontos,
+            CodigoErro::TokenInesperado,
+            "Esperado ':' no operador ternário",
+        )?;
+        let senao = self.parse_ternario()?;
+
+        let span = condicao.span.ate(senao.span);
+        Ok(Expr::new(
+            ExprKind::Ternario {
+                condicao: Box::new(condicao),
+                entao: Box::new(entao),
+                senao: Box::new(senao),
+            },
+            span,
+        ))
+    }
 
-            match expr_esquerda {
-                Expr::Variavel(_) | Expr::AcessoArray { .. } | Expr::Unario { .. } => {
-                    return Expr::AtribuicaoComposta {
-                        alvo: Box::new(expr_esquerda),
-                        operador,
-                        valor: Box::new(valor),
-                    };
+    // expressão completa, com o operador vírgula; argumentos de chamada e
+    // inicializadores usam parse_atribuicao porque ali a vírgula é separador
+    pub fn parse_virgula(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let mut expr = self.parse_atribuicao()?;
+
+        while self.token_atual == Token::Virgula {
+            self.avancar();
+            let direita = self.parse_atribuicao()?;
+            let span = expr.span.ate(direita.span);
+            expr = Expr::new(
+                ExprKind::Virgula {
+                    esquerda: Box::new(expr),
+                    direita: Box::new(direita),
+                },
+                span,
+            );
+        }
+        Ok(expr)
+    }
+
+    // uma expressão que precisa ocupar todos os tokens, como a condição de um '#if'
+    pub(crate) fn parse_expressao_isolada(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let expr = self.parse_ternario()?;
+
+        if self.token_atual != Token::Fundo {
+            return Err(self.erro(
+                CodigoErro::TokenInesperado,
+                format!(
+                    "Token inesperado {:?} depois do fim da expressão",
+                    self.token_atual
+                ),
+            ));
+        }
+        Ok(expr)
+    }
+
+    pub fn parse_atribuicao(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let expr_esquerda = self.parse_ternario()?;
+
+        if self.token_atual == Token::Igual {
+            self.avancar();
+            let valor = self.parse_atribuicao()?;
+
+            match expr_esquerda.kind {
+                ExprKind::Variavel(_)
+                | ExprKind::AcessoArray { .. }
+                | ExprKind::AcessoMembro { .. }
+                | ExprKind::Unario { .. } => {
+                    let span = expr_esquerda.span.ate(valor.span);
+                    return Ok(Expr::new(
+                        ExprKind::Atribuicao {
+                            alvo: Box::new(expr_esquerda),
+                            valor: Box::new(valor),
+                        },
+                        span,
+                    ));
+                }
+                _ => {
+                    return Err(Diagnostic::erro(
+                        CodigoErro::AlvoAtribuicaoInvalido,
+                        format!(
+                            "Erro de Sintaxe: Alvo inválido para atribuição. A expressão da esquerda é: {:?}",
+                            expr_esquerda.kind
+                        ),
+                        expr_esquerda.span,
+                    ));
+                }
+            }
+        } else if let Token::SomaIgual
+        | Token::SubtracaoIgual
+        | Token::MultiplicacaoIgual
+        | Token::DivisaoIgual
+        | Token::ModuloIgual
+        | Token::EComercialIgual
+        | Token::BarraVerticalIgual
+        | Token::CircunflexoIgual
+        | Token::DeslocamentoEsqIgual
+        | Token::DeslocamentoDirIgual = &self.token_atual
+        {
+            let operador = self.token_atual.clone();
+            self.avancar();
+            let valor = self.parse_atribuicao()?;
+
+            match expr_esquerda.kind {
+                ExprKind::Variavel(_)
+                | ExprKind::AcessoArray { .. }
+                | ExprKind::AcessoMembro { .. }
+                | ExprKind::Unario { .. } => {
+                    let span = expr_esquerda.span.ate(valor.span);
+                    return Ok(Expr::new(
+                        ExprKind::AtribuicaoComposta {
+                            alvo: Box::new(expr_esquerda),
+                            operador,
+                            valor: Box::new(valor),
+                        },
+                        span,
+                    ));
+                }
+                _ => {
+                    return Err(Diagnostic::erro(
+                        CodigoErro::AlvoAtribuicaoInvalido,
+                        format!(
+                            "Erro de Sintaxe: Alvo inválido para atribuição composta. A expressão da esquerda é: {:?}",
+                            expr_esquerda.kind
+                        ),
+                        expr_esquerda.span,
+                    ));
                 }
-                _ => self.erro(format!("Erro de Sintaxe: Alvo inválido para atribuição composta. A expressão da esquerda é: {:?}", expr_esquerda)),
             }
         }
-        expr_esquerda
+        Ok(expr_esquerda)
     }
 
-    pub fn parse_declaracao(&mut self) -> Stmt {
+    pub fn parse_declaracao(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+
         match &self.token_atual {
             Token::InclusaoGlobal(_) | Token::InclusaoLocal(_) => self.parse_diretiva_inclusao(),
 
@@ -497,35 +1135,114 @@ impl Parser {
 
             Token::AbreChave => self.parse_bloco(),
 
-            Token::Identificador(nome) if nome == "if" => {
-                return self.parse_declaracao_if();
+            Token::PontoVirgula => {
+                self.avancar();
+                Ok(Stmt::new(StmtKind::Vazia, inicio))
+            }
+
+            Token::PalavraChave(PalavraChave::If) => self.parse_declaracao_if(),
+
+            Token::PalavraChave(PalavraChave::While) => self.parse_declaracao_while(),
+
+            Token::PalavraChave(PalavraChave::Do) => self.parse_declaracao_do_while(),
+
+            Token::PalavraChave(PalavraChave::For) => self.parse_declaracao_for(),
+
+            Token::PalavraChave(PalavraChave::Switch) => self.parse_declaracao_switch(),
+
+            Token::PalavraChave(palavra @ (PalavraChave::Case | PalavraChave::Default)) => {
+                Err(self.erro(
+                    CodigoErro::TokenInesperado,
+                    format!("'{}' fora de um 'switch'", palavra.como_str()),
+                ))
             }
 
-            Token::Identificador(nome) if nome == "return" => {
+            Token::PalavraChave(palavra @ (PalavraChave::Break | PalavraChave::Continue)) => {
+                let kind = if *palavra == PalavraChave::Break {
+                    StmtKind::Break
+                } else {
+                    StmtKind::Continue
+                };
+                let mensagem = format!("Esperado ';' após '{}'", palavra.como_str());
+                self.avancar();
+                self.esperar(
+                    Token::PontoVirgula,
+                    CodigoErro::PontoVirgulaEsperado,
+                    &mensagem,
+                )?;
+                Ok(Stmt::new(kind, self.span_desde(inicio)))
+            }
+
+            Token::PalavraChave(PalavraChave::Goto) => {
+                self.avancar();
+
+                let rotulo = if let Token::Identificador(rotulo) = &self.token_atual {
+                    rotulo.clone()
+                } else {
+                    return Err(self.erro(
+                        CodigoErro::NomeEsperado,
+                        format!(
+                            "Esperado nome do rótulo após 'goto', mas foi recebido {:?}",
+                            self.token_atual
+                        ),
+                    ));
+                };
+                self.avancar();
+
+                self.esperar(
+                    Token::PontoVirgula,
+                    CodigoErro::PontoVirgulaEsperado,
+                    "Esperado ';' após 'goto'",
+                )?;
+                Ok(Stmt::new(
+                    StmtKind::Goto(rotulo.into_owned()),
+                    self.span_desde(inicio),
+                ))
+            }
+
+            Token::Identificador(nome) if self.espiadinha() == Token::DoisPontos => {
+                let nome = nome.to_string();
+                self.avancar();
+                self.avancar();
+
+                let declaracao = self.parse_declaracao()?;
+
+                Ok(Stmt::new(
+                    StmtKind::Rotulo {
+                        nome,
+                        declaracao: Box::new(declaracao),
+                    },
+                    self.span_desde(inicio),
+                ))
+            }
+
+            Token::PalavraChave(PalavraChave::Return) => {
                 self.avancar();
 
                 let valor = if self.token_atual == Token::PontoVirgula {
                     None
                 } else {
-                    Some(self.parse_atribuicao())
+                    Some(self.parse_virgula()?)
                 };
 
                 if self.token_atual != Token::PontoVirgula {
-                    self.erro("esperado ';' após return".to_string());
+                    return Err(self.erro(
+                        CodigoErro::PontoVirgulaEsperado,
+                        "esperado ';' após return".to_string(),
+                    ));
                 }
                 self.avancar();
-                return Stmt::Retorno(valor);
+                Ok(Stmt::new(StmtKind::Retorno(valor), self.span_desde(inicio)))
             }
 
-            Token::Identificador(nome) if nome == "using" => {
+            Token::PalavraChave(PalavraChave::Using) => {
                 self.avancar();
 
-                if let Token::Identificador(ns_kw) = &self.token_atual {
-                    if ns_kw != "namespace" {
-                        self.erro("Esperado 'namespace' após 'using'".to_string());
-                    }
-                } else {
-                    self.erro("Esperado 'namespace' após 'using'".to_string());
+                if self.token_atual != Token::PalavraChave(PalavraChave::Namespace) {
+                    return Err(self.erro(
+                        CodigoErro::TokenInesperado,
+                        "Esperado 'namespace' após 'using'".to_string(),
+                    ));
                 }
 
                 self.avancar();
@@ -533,366 +1250,1353 @@ impl Parser {
                 let namespace_nome = if let Token::Identificador(nome) = &self.token_atual {
                     nome.clone()
                 } else {
-                    self.erro("Esperado nome do namespace".to_string());
-                    unreachable!()
+                    return Err(self.erro(
+                        CodigoErro::NomeEsperado,
+                        "Esperado nome do namespace".to_string(),
+                    ));
                 };
 
                 self.avancar();
 
                 if self.token_atual != Token::PontoVirgula {
-                    self.erro("esperado ';' após using namespace".to_string());
+                    return Err(self.erro(
+                        CodigoErro::PontoVirgulaEsperado,
+                        "esperado ';' após using namespace".to_string(),
+                    ));
                 }
 
                 self.avancar();
 
-                return Stmt::Using {
-                    namespace: namespace_nome,
-                };
+                Ok(Stmt::new(
+                    StmtKind::Using {
+                        namespace: namespace_nome.into_owned(),
+                    },
+                    self.span_desde(inicio),
+                ))
             }
 
-            Token::Identificador(_) => {
-                if let Token::Identificador(_) = self.espiadinha() {
-                    if let Token::AbreParentesis = self.espiar_dois_passos() {
-                        self.parse_declaracao_funcao()
-                    } else {
-                        self.parse_declaracao_variavel()
-                    }
-                } else if let Token::Asterisco = self.espiadinha() {
-                    self.parse_declaracao_variavel()
-                } else {
-                    self.parse_declaracao_expressao()
-                }
+            // no dialeto C, o padrão, 'using' e 'namespace' são nomes comuns; sem este caso
+            // o erro apareceria só no ';' esperado depois de 'using namespace'
+            Token::Identificador(nome)
+                if nome == "using"
+                    && matches!(self.espiadinha(), Token::Identificador(proximo) if proximo == "namespace") =>
+            {
+                Err(self.erro(
+                    CodigoErro::TokenInesperado,
+                    "'using namespace' só existe em C++; use Lexer::com_dialeto(Dialeto::Cpp)"
+                        .to_string(),
+                ))
+            }
+
+            Token::Identificador(_) | Token::PalavraChave(_) if self.inicia_declaracao() => {
+                self.parse_declaracao_tipada()
             }
 
             _ => self.parse_declaracao_expressao(),
         }
     }
 
-    pub fn parse(&mut self) -> Vec<Stmt> {
+    // falha só quando há erros, como Preprocessador::processar; avisos aparecem apenas
+    // em parse_parcial
+    pub fn parse(&mut self) -> Result<Vec<Stmt<'a>>, Vec<Diagnostic>> {
+        let (declaracoes, diagnosticos) = self.parse_parcial();
+
+        if diagnosticos
+            .iter()
+            .any(|diagnostico| diagnostico.severidade == Severidade::Erro)
+        {
+            Err(diagnosticos)
+        } else {
+            Ok(declaracoes)
+        }
+    }
+
+    // analisa tudo o que for possível, devolvendo a árvore parcial (com StmtKind::Erro)
+    // junto com todos os diagnósticos encontrados
+    pub fn parse_parcial(&mut self) -> (Vec<Stmt<'a>>, Vec<Diagnostic>) {
         let mut declaracoes: Vec<Stmt> = Vec::new();
 
         while self.token_atual != Token::Fundo {
-            if self.token_atual == Token::QuebraLinha {
-                self.avancar();
-                continue;
-            }
-            declaracoes.push(self.parse_declaracao());
+            declaracoes.push(self.parse_declaracao_recuperando());
         }
-        declaracoes
+        (declaracoes, std::mem::take(&mut self.diagnosticos))
     }
 
-    pub fn parse_diretiva_inclusao(&mut self) -> Stmt {
+    pub fn parse_diretiva_inclusao(&mut self) -> Result<Stmt<'a>, Diagnostic> {
         let token_clonado = self.token_atual.clone();
+        let span = self.span_atual;
         self.avancar();
 
-        match token_clonado {
-            Token::InclusaoGlobal(path) => Stmt::Inclusao {
-                path,
+        let kind = match token_clonado {
+            Token::InclusaoGlobal(path) => StmtKind::Inclusao {
+                path: path.into_owned(),
                 is_global: true,
             },
-            Token::InclusaoLocal(path) => Stmt::Inclusao {
-                path,
+            Token::InclusaoLocal(path) => StmtKind::Inclusao {
+                path: path.into_owned(),
                 is_global: false,
             },
             _ => unreachable!(),
-        }
+        };
+
+        Ok(Stmt::new(kind, span))
     }
 
-    pub fn parse_declaracao_if(&mut self) -> Stmt {
+    pub fn parse_declaracao_if(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
         self.avancar();
 
         if self.token_atual != Token::AbreParentesis {
-            self.erro(format!(
-                "Esperado '(' após 'if', mas foi recebido {:?}",
-                self.token_atual
+            return Err(self.erro(
+                CodigoErro::ParentesisEsperado,
+                format!(
+                    "Esperado '(' após 'if', mas foi recebido {:?}",
+                    self.token_atual
+                ),
             ));
         }
 
         self.avancar();
-        let condicao = self.parse_atribuicao();
+        let condicao = self.parse_virgula()?;
 
         if self.token_atual != Token::FechaParentesis {
-            self.erro(format!(
-                "Esperado ')' após condição do 'if', mas foi recebido {:?}",
-                self.token_atual
+            return Err(self.erro(
+                CodigoErro::ParentesisEsperado,
+                format!(
+                    "Esperado ')' após condição do 'if', mas foi recebido {:?}",
+                    self.token_atual
+                ),
             ));
         }
 
         self.avancar();
-        let bloco_then = self.parse_declaracao();
+        let bloco_then = self.parse_declaracao()?;
 
         let mut bloco_else: Option<Box<Stmt>> = None;
 
-        if let Token::Identificador(nome) = &self.token_atual {
-            if nome == "else" {
-                self.avancar();
-                if let Token::Identificador(nome) = &self.token_atual {
-                    if nome == "if" {
-                        bloco_else = Some(Box::new(self.parse_declaracao_if()));
-                    } else {
-                        bloco_else = Some(Box::new(self.parse_declaracao()));
-                    }
+        if self.token_atual == Token::PalavraChave(PalavraChave::Else) {
+            self.avancar();
+            if self.token_atual == Token::PalavraChave(PalavraChave::If) {
+                bloco_else = Some(Box::new(self.parse_declaracao_if()?));
+            } else {
+                bloco_else = Some(Box::new(self.parse_declaracao()?));
+            }
+        }
+
+        Ok(Stmt::new(
+            StmtKind::If {
+                condicao,
+                bloco_then: Box::new(bloco_then),
+                bloco_else,
+            },
+            self.span_desde(inicio),
+        ))
+    }
+
+    pub fn parse_declaracao_while(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        self.avancar();
+
+        self.esperar(
+            Token::AbreParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado '(' após 'while'",
+        )?;
+        let condicao = self.parse_virgula()?;
+        self.esperar(
+            Token::FechaParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado ')' após condição do 'while'",
+        )?;
+
+        let corpo = self.parse_declaracao()?;
+
+        Ok(Stmt::new(
+            StmtKind::While {
+                condicao,
+                corpo: Box::new(corpo),
+            },
+            self.span_desde(inicio),
+        ))
+    }
+
+    pub fn parse_declaracao_do_while(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        self.avancar();
+
+        let corpo = self.parse_declaracao()?;
+
+        if self.token_atual != Token::PalavraChave(PalavraChave::While) {
+            return Err(self.erro(
+                CodigoErro::TokenInesperado,
+                format!(
+                    "Esperado 'while' após o corpo do 'do', mas foi recebido {:?}",
+                    self.token_atual
+                ),
+            ));
+        }
+        self.avancar();
+
+        self.esperar(
+            Token::AbreParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado '(' após 'while'",
+        )?;
+        let condicao = self.parse_virgula()?;
+        self.esperar(
+            Token::FechaParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado ')' após condição do 'do-while'",
+        )?;
+        self.esperar(
+            Token::PontoVirgula,
+            CodigoErro::PontoVirgulaEsperado,
+            "Esperado ';' após 'do-while'",
+        )?;
+
+        Ok(Stmt::new(
+            StmtKind::DoWhile {
+                corpo: Box::new(corpo),
+                condicao,
+            },
+            self.span_desde(inicio),
+        ))
+    }
+
+    // for (inicialização; condição; incremento) — qualquer uma das três partes pode faltar
+    pub fn parse_declaracao_for(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        self.avancar();
+
+        self.esperar(
+            Token::AbreParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado '(' após 'for'",
+        )?;
+
+        // a inicialização consome o próprio ';'
+        let inicializacao = if self.token_atual == Token::PontoVirgula {
+            self.avancar();
+            None
+        } else if self.inicia_declaracao() {
+            Some(Box::new(self.parse_declaracao_tipada()?))
+        } else {
+            Some(Box::new(self.parse_declaracao_expressao()?))
+        };
+
+        let condicao = if self.token_atual == Token::PontoVirgula {
+            None
+        } else {
+            Some(self.parse_virgula()?)
+        };
+        self.esperar(
+            Token::PontoVirgula,
+            CodigoErro::PontoVirgulaEsperado,
+            "Esperado ';' após condição do 'for'",
+        )?;
+
+        let incremento = if self.token_atual == Token::FechaParentesis {
+            None
+        } else {
+            Some(self.parse_virgula()?)
+        };
+        self.esperar(
+            Token::FechaParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado ')' após as cláusulas do 'for'",
+        )?;
+
+        let corpo = self.parse_declaracao()?;
+
+        Ok(Stmt::new(
+            StmtKind::For {
+                inicializacao,
+                condicao,
+                incremento,
+                corpo: Box::new(corpo),
+            },
+            self.span_desde(inicio),
+        ))
+    }
+
+    pub fn parse_declaracao_switch(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        self.avancar();
+
+        self.esperar(
+            Token::AbreParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado '(' após 'switch'",
+        )?;
+        let expressao = self.parse_virgula()?;
+        self.esperar(
+            Token::FechaParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado ')' após expressão do 'switch'",
+        )?;
+        self.esperar(
+            Token::AbreChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '{' para iniciar o corpo do 'switch'",
+        )?;
+
+        self.profundidade_bloco += 1;
+        let mut casos: Vec<CasoSwitch> = Vec::new();
+
+        while self.token_atual != Token::FechaChave && self.token_atual != Token::Fundo {
+            let inicio_caso = self.span_atual;
+            let is_rotulo = matches!(
+                self.token_atual,
+                Token::PalavraChave(PalavraChave::Case | PalavraChave::Default)
+            );
+
+            if !is_rotulo {
+                if let Some(caso) = casos.last_mut() {
+                    let declaracao = self.parse_declaracao_recuperando();
+                    caso.span = caso.span.ate(declaracao.span);
+                    caso.declaracoes.push(declaracao);
                 } else {
-                    bloco_else = Some(Box::new(self.parse_declaracao()));
+                    let diagnostico = self.erro(
+                        CodigoErro::TokenInesperado,
+                        "Declaração antes do primeiro 'case' do 'switch'".to_string(),
+                    );
+                    self.diagnosticos.push(diagnostico);
+                    self.parse_declaracao_recuperando();
                 }
+                continue;
             }
-        }
 
-        Stmt::If {
-            condicao,
-            bloco_then: Box::new(bloco_then),
-            bloco_else,
+            match self.parse_rotulo_caso() {
+                Ok(valor) => casos.push(CasoSwitch {
+                    valor,
+                    declaracoes: Vec::new(),
+                    span: self.span_desde(inicio_caso),
+                }),
+                Err(diagnostico) => {
+                    self.diagnosticos.push(diagnostico);
+                    self.sincronizar();
+                }
+            }
         }
+        self.profundidade_bloco -= 1;
+
+        self.esperar(
+            Token::FechaChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '}' para fechar o 'switch'",
+        )?;
+
+        Ok(Stmt::new(
+            StmtKind::Switch { expressao, casos },
+            self.span_desde(inicio),
+        ))
+    }
+
+    // 'case <expressão constante>:' ou 'default:'
+    fn parse_rotulo_caso(&mut self) -> Result<Option<Expr<'a>>, Diagnostic> {
+        let valor = if self.token_atual == Token::PalavraChave(PalavraChave::Case) {
+            self.avancar();
+            Some(self.parse_ternario()?)
+        } else {
+            self.avancar();
+            None
+        };
+
+        self.esperar(
+            Token::DoisPontos,
+            CodigoErro::TokenInesperado,
+            "Esperado ':' após rótulo do 'switch'",
+        )?;
+
+        Ok(valor)
     }
 
-    pub fn parse_bloco(&mut self) -> Stmt {
+    pub fn parse_bloco(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+
         if self.token_atual != Token::AbreChave {
-            self.erro(format!(
-                "Esperado '{{' para iniciar o bloco, mas foi recebido {:?}",
-                self.token_atual
+            return Err(self.erro(
+                CodigoErro::ChaveEsperada,
+                format!(
+                    "Esperado '{{' para iniciar o bloco, mas foi recebido {:?}",
+                    self.token_atual
+                ),
             ));
         }
 
         self.avancar();
+        self.profundidade_bloco += 1;
         let mut declaracoes: Vec<Stmt> = Vec::new();
 
         while self.token_atual != Token::FechaChave && self.token_atual != Token::Fundo {
-            if self.token_atual == Token::QuebraLinha {
-                self.avancar();
-                continue;
-            }
-            declaracoes.push(self.parse_declaracao());
+            declaracoes.push(self.parse_declaracao_recuperando());
         }
+        self.profundidade_bloco -= 1;
 
+        // o bloco só pode terminar no '}' ou no fim do arquivo; no segundo caso
+        // mantemos o que já foi analisado
         if self.token_atual != Token::FechaChave {
-            self.erro(format!(
-                "Esperado '}}' para fechar o bloco, mas foi recebido {:?}",
-                self.token_atual
+            let diagnostico = self.erro(
+                CodigoErro::ChaveEsperada,
+                format!(
+                    "Esperado '}}' para fechar o bloco, mas foi recebido {:?}",
+                    self.token_atual
+                ),
+            );
+            self.diagnosticos.push(diagnostico);
+            return Ok(Stmt::new(
+                StmtKind::Bloco { declaracoes },
+                self.span_desde(inicio),
             ));
         }
 
         self.avancar();
-        Stmt::Bloco { declaracoes }
+        Ok(Stmt::new(
+            StmtKind::Bloco { declaracoes },
+            self.span_desde(inicio),
+        ))
     }
 
-    pub fn parse_diretiva_outra(&mut self) -> Stmt {
+    pub fn parse_diretiva_outra(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
         let comando = if let Token::Diretiva(cmd) = &self.token_atual {
             cmd.clone()
         } else {
             unreachable!()
         };
 
+        // só linhas de diretiva terminam com Token::QuebraLinha
         self.avancar();
+        let mut argumentos = Vec::new();
         while self.token_atual != Token::Fundo && self.token_atual != Token::QuebraLinha {
+            argumentos.push(self.token_atual.clone());
+            self.avancar();
+        }
+        let span = self.span_desde(inicio);
+        if self.token_atual == Token::QuebraLinha {
             self.avancar();
         }
 
-        Stmt::Diretiva(comando)
+        if comando == "pragma" {
+            let pragma = self.interpretar_pragma(argumentos, span);
+            return Ok(Stmt::new(StmtKind::Pragma(pragma), span));
+        }
+
+        Ok(Stmt::new(StmtKind::Diretiva(comando.into_owned()), span))
     }
 
-    pub fn parse_declaracao_funcao(&mut self) -> Stmt {
-        let mut tipo_retorno: Vec<Token> = Vec::new();
+    // a linha já foi consumida, então um 'pack' malformado só gera o diagnóstico e
+    // fica como Pragma::Outro
+    fn interpretar_pragma(&mut self, argumentos: Vec<Token<'a>>, span: Span) -> Pragma<'a> {
+        let nome = match argumentos.first() {
+            Some(Token::Identificador(nome)) => nome.to_string(),
+            _ => String::new(),
+        };
 
-        while self.token_atual != Token::Fundo {
-            if let Token::Identificador(_) = self.token_atual {
-                if let Token::AbreParentesis = self.espiadinha() {
-                    break;
+        match nome.as_str() {
+            "once" if argumentos.len() == 1 => Pragma::Once,
+            "pack" => match interpretar_pack(&argumentos[1..]) {
+                Ok((operacao, alinhamento)) => Pragma::Pack {
+                    operacao,
+                    alinhamento,
+                },
+                Err(mensagem) => {
+                    self.diagnosticos.push(Diagnostic::erro(
+                        CodigoErro::DiretivaInvalida,
+                        mensagem,
+                        span,
+                    ));
+                    Pragma::Outro {
+                        nome,
+                        argumentos: argumentos[1..].to_vec(),
+                    }
                 }
+            },
+            _ => Pragma::Outro {
+                nome,
+                argumentos: argumentos.into_iter().skip(1).collect(),
+            },
+        }
+    }
+
+    // a declaração começa com um tipo? palavras de tipo e nomes de typedef sempre começam;
+    // um nome desconhecido só quando parece um tipo externo ('FILE *f;')
+    fn inicia_declaracao(&self) -> bool {
+        match &self.token_atual {
+            Token::PalavraChave(palavra) => inicia_tipo(*palavra),
+            Token::Identificador(nome) => {
+                self.typedefs.contains(nome.as_ref()) || self.parece_tipo_desconhecido()
             }
-            tipo_retorno.push(self.token_atual.clone());
-            self.avancar();
+            _ => false,
+        }
+    }
+
+    // nome desconhecido seguido de outro nome, direto ou depois de '*' ('FILE *f',
+    // 'string **v'); 'x * 2' continua sendo expressão, e 'a * b;' também quando 'a' já
+    // foi declarado como variável. sem nenhuma das duas declarações fica como declaração
+    fn parece_tipo_desconhecido(&self) -> bool {
+        if let Token::Identificador(nome) = &self.token_atual
+            && self.variaveis.contains(nome.as_ref())
+        {
+            return false;
         }
 
-        if !matches!(self.token_atual, Token::Identificador(_)) {
-            self.erro(format!(
-                "Esperado nome da função após o tipo de retorno, mas foi recebido {:?}",
-                self.token_atual
+        let mut passos = 1;
+        while self.espiar(passos) == Token::Asterisco {
+            passos += 1;
+        }
+        matches!(self.espiar(passos), Token::Identificador(_))
+    }
+
+    // diferente de inicia_declaracao, não adivinha por 'Nome Nome': dentro de parênteses
+    // '(a * b)' precisa continuar sendo uma expressão
+    fn e_nome_tipo(&self, token: &Token<'a>) -> bool {
+        match token {
+            Token::PalavraChave(palavra) => inicia_tipo(*palavra),
+            Token::Identificador(nome) => self.typedefs.contains(nome.as_ref()),
+            _ => false,
+        }
+    }
+
+    // tipo sem nome, como em 'sizeof(int *)' e '(const char **)p'
+    fn parse_nome_tipo(&mut self) -> Result<Tipo<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        let (armazenamento, base) = self.parse_especificadores()?;
+
+        if armazenamento.is_some() {
+            return Err(Diagnostic::erro(
+                CodigoErro::TipoInvalido,
+                "Classe de armazenamento não é permitida num nome de tipo".to_string(),
+                self.span_desde(inicio),
             ));
         }
 
-        let nome = self.token_atual.clone();
-        self.avancar();
+        let (nome, tipo) = self.parse_declarador(base)?;
+        if let Some(nome) = nome {
+            return Err(Diagnostic::erro(
+                CodigoErro::TipoInvalido,
+                format!("Nome de tipo não pode declarar {:?}", nome),
+                self.span_anterior,
+            ));
+        }
+        Ok(tipo)
+    }
 
-        if self.token_atual != Token::AbreParentesis {
-            self.erro(format!(
-                "Esperado '(' após o nome da função, mas foi recebido {:?}",
-                self.token_atual
+    // declaração que começa por um tipo: variável, protótipo ou definição de função.
+    // 'struct Nome { ... } variáveis;', 'struct Nome;' e 'typedef struct { ... } Nome;'
+    // viram a definição do agregado, com as variáveis em 'declaracao'
+    pub fn parse_declaracao_tipada(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        let (armazenamento, mut base) = self.parse_especificadores()?;
+
+        // o corpo passa para o Stmt; as variáveis declaradas junto ficam só com o nome
+        let (agregado, definicao) = match &mut base {
+            Tipo::Base {
+                base: agregado @ (TipoBase::Struct(_) | TipoBase::Union(_) | TipoBase::Enum(_)),
+                definicao,
+                ..
+            } if definicao.is_some() || self.token_atual == Token::PontoVirgula => {
+                (agregado.clone(), definicao.take())
+            }
+            _ => return self.parse_resto_declaracao(inicio, armazenamento, base),
+        };
+        let declaracao = self.parse_declaracao_apos_definicao(inicio, armazenamento, base)?;
+
+        let (campos, enumeradores) = match definicao {
+            Some(Definicao::Campos(campos)) => (Some(campos), None),
+            Some(Definicao::Enumeradores(enumeradores)) => (None, Some(enumeradores)),
+            None => (None, None),
+        };
+        let kind = match agregado {
+            TipoBase::Enum(nome) => StmtKind::Enum {
+                nome,
+                enumeradores,
+                declaracao,
+            },
+            TipoBase::Union(nome) => StmtKind::Union {
+                nome,
+                campos,
+                declaracao,
+            },
+            TipoBase::Struct(nome) => StmtKind::Struct {
+                nome,
+                campos,
+                declaracao,
+            },
+            _ => unreachable!(),
+        };
+
+        Ok(Stmt::new(kind, self.span_desde(inicio)))
+    }
+
+    fn parse_resto_declaracao(
+        &mut self,
+        inicio: Span,
+        armazenamento: Option<Armazenamento>,
+        base: Tipo<'a>,
+    ) -> Result<Stmt<'a>, Diagnostic> {
+        let mut declaradores = Vec::new();
+
+        loop {
+            let inicio_declarador = self.span_atual;
+            let (nome, tipo) = self.parse_declarador(base.clone())?;
+
+            let nome = match nome {
+                Some(nome) => nome,
+                None => {
+                    return Err(self.erro(
+                        CodigoErro::NomeEsperado,
+                        format!(
+                            "Esperado nome na declaração, mas foi recebido {:?}",
+                            self.token_atual
+                        ),
+                    ));
+                }
+            };
+
+            if armazenamento != Some(Armazenamento::Typedef)
+                && let Token::Identificador(nome) = &nome
+            {
+                self.variaveis.insert(nome.to_string());
+            }
+
+            // um único declarador de função seguido de '{' é uma definição de função
+            if declaradores.is_empty()
+                && self.token_atual == Token::AbreChave
+                && let Tipo::Funcao {
+                    retorno,
+                    parametros,
+                    variadica,
+                } = tipo
+            {
+                let nome_funcao = match &nome {
+                    Token::Identificador(nome) => Some(nome.to_string()),
+                    _ => None,
+                };
+                let anterior = std::mem::replace(&mut self.funcao_atual, nome_funcao);
+                let corpo = self.parse_bloco();
+                self.funcao_atual = anterior;
+                let corpo = corpo?;
+
+                return Ok(Stmt::new(
+                    StmtKind::DeclaracaoFuncao {
+                        armazenamento,
+                        tipo_retorno: *retorno,
+                        nome,
+                        parametros,
+                        variadica,
+                        corpo: Box::new(corpo),
+                    },
+                    self.span_desde(inicio),
+                ));
+            }
+
+            if armazenamento == Some(Armazenamento::Typedef)
+                && let Token::Identificador(nome) = &nome
+            {
+                self.typedefs.insert(nome.to_string());
+            }
+
+            let inicializador = if self.token_atual == Token::Igual {
+                self.avancar();
+                Some(self.parse_inicializador()?)
+            } else {
+                None
+            };
+
+            declaradores.push(Declarador {
+                tipo,
+                nome,
+                inicializador,
+                span: self.span_desde(inicio_declarador),
+            });
+
+            if self.token_atual != Token::Virgula {
+                break;
+            }
+            self.avancar();
+        }
+
+        if self.token_atual != Token::PontoVirgula {
+            return Err(self.erro(
+                CodigoErro::PontoVirgulaEsperado,
+                format!(
+                    "Esperado ';' após declaração de variável, mas foi recebido {:?}",
+                    self.token_atual
+                ),
             ));
         }
 
         self.avancar();
-        let mut parametros: Vec<Parametro> = Vec::new();
 
-        if self.token_atual != Token::FechaParentesis {
+        Ok(Stmt::new(
+            StmtKind::DeclaracaoVariavel {
+                armazenamento,
+                declaradores,
+            },
+            self.span_desde(inicio),
+        ))
+    }
+
+    // o valor depois do '=' numa declaração; uma lista entre chaves ou uma expressão
+    // sem o operador vírgula, que ali separa declaradores
+    fn parse_inicializador(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        if self.token_atual == Token::AbreChave {
+            return self.parse_lista_inicializacao();
+        }
+        self.parse_atribuicao()
+    }
+
+    // '{ 1, [3] = 2, .x = { 0 }, }', com vírgula final opcional
+    fn parse_lista_inicializacao(&mut self) -> Result<Expr<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        self.esperar(
+            Token::AbreChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '{' no início da lista de inicialização",
+        )?;
+
+        let mut itens = Vec::new();
+
+        while self.token_atual != Token::FechaChave {
+            let inicio_item = self.span_atual;
+            let mut designadores = Vec::new();
+
             loop {
-                let mut tipo_param: Vec<Token> = Vec::new();
-                let mut tamanho_array: Option<Expr> = None;
-
-                while self.token_atual != Token::Fundo {
-                    if let Token::Identificador(_) = self.token_atual {
-                        if let Token::Virgula | Token::FechaParentesis | Token::AbreColchete =
-                            self.espiadinha()
-                        {
-                            break;
-                        }
+                match self.token_atual {
+                    Token::Ponto => {
+                        self.avancar();
+                        let Token::Identificador(membro) = self.token_atual.clone() else {
+                            return Err(self.erro(
+                                CodigoErro::NomeEsperado,
+                                format!(
+                                    "Esperado nome de membro após '.', mas foi recebido {:?}",
+                                    self.token_atual
+                                ),
+                            ));
+                        };
+                        self.avancar();
+                        designadores.push(Designador::Membro(membro.into_owned()));
                     }
-                    tipo_param.push(self.token_atual.clone());
-                    self.avancar();
+                    Token::AbreColchete => {
+                        self.avancar();
+                        let indice = self.parse_ternario()?;
+                        self.esperar(
+                            Token::FechaColchete,
+                            CodigoErro::ColcheteEsperado,
+                            "Esperado ']' após o índice do designador",
+                        )?;
+                        designadores.push(Designador::Indice(indice));
+                    }
+                    _ => break,
                 }
+            }
 
-                if !matches!(self.token_atual, Token::Identificador(_)) {
-                    self.erro(format!(
-                        "Esperado nome do parâmetro na declaração da função, mas foi recebido {:?}",
-                        self.token_atual
-                    ));
-                }
+            if !designadores.is_empty() {
+                self.esperar(
+                    Token::Igual,
+                    CodigoErro::TokenInesperado,
+                    "Esperado '=' após o designador",
+                )?;
+            }
 
-                let nome_param = self.token_atual.clone();
-                self.avancar();
+            let valor = self.parse_inicializador()?;
+            itens.push(ItemInicializacao {
+                designadores,
+                valor,
+                span: self.span_desde(inicio_item),
+            });
+
+            if self.token_atual != Token::Virgula {
+                break;
+            }
+            self.avancar();
+        }
+
+        self.esperar(
+            Token::FechaChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '}' no fim da lista de inicialização",
+        )?;
 
-                if self.token_atual == Token::AbreColchete {
+        Ok(Expr::new(
+            ExprKind::ListaInicializacao(itens),
+            self.span_desde(inicio),
+        ))
+    }
+
+    // lê 'static const unsigned long' e similares, em qualquer ordem
+    fn parse_especificadores(&mut self) -> Result<(Option<Armazenamento>, Tipo<'a>), Diagnostic> {
+        let inicio = self.span_atual;
+        let mut especificadores = Especificadores::default();
+
+        loop {
+            let palavra = match &self.token_atual {
+                Token::PalavraChave(palavra) => *palavra,
+                // nome de tipo: só quando ainda não há tipo, senão é o nome declarado
+                Token::Identificador(nome)
+                    if especificadores.sem_tipo()
+                        && (self.typedefs.contains(nome.as_ref())
+                            || self.parece_tipo_desconhecido()) =>
+                {
+                    let base = TipoBase::Nomeado(nome.to_string());
+                    self.definir_base(&mut especificadores, base)?;
                     self.avancar();
-                    if self.token_atual == Token::FechaColchete {
-                        tamanho_array = Some(Expr::ArrayDim);
-                    } else {
-                        tamanho_array = Some(self.parse_atribuicao());
+                    continue;
+                }
+                _ => break,
+            };
+
+            match palavra {
+                PalavraChave::Typedef
+                | PalavraChave::Extern
+                | PalavraChave::Static
+                | PalavraChave::Auto
+                | PalavraChave::Register => {
+                    if especificadores.armazenamento.is_some() {
+                        return Err(self.erro(
+                            CodigoErro::TipoInvalido,
+                            "Mais de uma classe de armazenamento na declaração".to_string(),
+                        ));
                     }
+                    especificadores.armazenamento = Some(match palavra {
+                        PalavraChave::Typedef => Armazenamento::Typedef,
+                        PalavraChave::Extern => Armazenamento::Extern,
+                        PalavraChave::Static => Armazenamento::Static,
+                        PalavraChave::Auto => Armazenamento::Auto,
+                        _ => Armazenamento::Register,
+                    });
+                }
 
-                    if self.token_atual != Token::FechaColchete {
-                        self.erro(format!(
-                            "Esperado ']' em parâmetro de array, mas foi recebido {:?}",
-                            self.token_atual
+                PalavraChave::Const => especificadores.qualificadores.constante = true,
+                PalavraChave::Volatile => especificadores.qualificadores.volatil = true,
+
+                // não mudam o tipo
+                PalavraChave::Inline | PalavraChave::Restrict => {}
+
+                PalavraChave::Signed | PalavraChave::Unsigned => {
+                    if especificadores.sinal.is_some() {
+                        return Err(self.erro(
+                            CodigoErro::TipoInvalido,
+                            "'signed'/'unsigned' repetido na declaração".to_string(),
                         ));
                     }
-                    self.avancar();
+                    especificadores.sinal = Some(if palavra == PalavraChave::Signed {
+                        Sinal::Signed
+                    } else {
+                        Sinal::Unsigned
+                    });
                 }
 
-                parametros.push(Parametro {
-                    tipo: tipo_param,
-                    nome: nome_param,
-                    tamanho_array,
-                });
+                PalavraChave::Short => especificadores.shorts += 1,
+                PalavraChave::Long => especificadores.longs += 1,
+                PalavraChave::Int => especificadores.is_int = true,
+
+                PalavraChave::Void
+                | PalavraChave::Char
+                | PalavraChave::Float
+                | PalavraChave::Double
+                | PalavraChave::Bool
+                | PalavraChave::_Bool => {
+                    let base = match palavra {
+                        PalavraChave::Void => TipoBase::Void,
+                        PalavraChave::Char => TipoBase::Char,
+                        PalavraChave::Float => TipoBase::Float,
+                        PalavraChave::Double => TipoBase::Double,
+                        _ => TipoBase::Bool,
+                    };
+                    self.definir_base(&mut especificadores, base)?;
+                }
 
-                if self.token_atual == Token::Virgula {
+                // 'struct Nome' usa um tipo definido em outro lugar; com '{' o corpo é
+                // definido aqui mesmo, e o nome fica opcional
+                PalavraChave::Struct | PalavraChave::Union | PalavraChave::Enum => {
                     self.avancar();
+                    let nome = if let Token::Identificador(nome) = &self.token_atual {
+                        let nome = nome.to_string();
+                        self.avancar();
+                        Some(nome)
+                    } else {
+                        None
+                    };
+
+                    if self.token_atual == Token::AbreChave {
+                        especificadores.definicao = Some(if palavra == PalavraChave::Enum {
+                            Definicao::Enumeradores(self.parse_enumeradores()?)
+                        } else {
+                            Definicao::Campos(self.parse_campos()?)
+                        });
+                    } else if nome.is_none() {
+                        return Err(self.erro(
+                            CodigoErro::NomeEsperado,
+                            format!(
+                                "Esperado nome ou '{{' após '{}', mas foi recebido {:?}",
+                                palavra.como_str(),
+                                self.token_atual
+                            ),
+                        ));
+                    }
+
+                    let base = match palavra {
+                        PalavraChave::Struct => TipoBase::Struct(nome),
+                        PalavraChave::Union => TipoBase::Union(nome),
+                        _ => TipoBase::Enum(nome),
+                    };
+                    self.definir_base(&mut especificadores, base)?;
                     continue;
                 }
 
-                if self.token_atual == Token::FechaParentesis {
-                    break;
-                } else {
-                    self.erro(format!(
-                        "Esperado ',' ou ')' após parâmetro de função, mas foi recebido {:?}",
-                        self.token_atual
-                    ));
-                }
+                _ => break,
             }
+
+            self.avancar();
         }
 
-        if self.token_atual != Token::FechaParentesis {
-            self.erro(format!(
-                "Esperado ')' após os parâmetros da função, mas foi recebido {:?}",
-                self.token_atual
+        let armazenamento = especificadores.armazenamento;
+        let tipo = Self::resolver_especificadores(especificadores).ok_or_else(|| {
+            Diagnostic::erro(
+                CodigoErro::TipoInvalido,
+                "Combinação inválida de especificadores de tipo".to_string(),
+                inicio.ate(self.span_anterior),
+            )
+        })?;
+
+        Ok((armazenamento, tipo))
+    }
+
+    fn definir_base(
+        &self,
+        especificadores: &mut Especificadores,
+        base: TipoBase,
+    ) -> Result<(), Diagnostic> {
+        if especificadores.base.is_some() {
+            return Err(self.erro(
+                CodigoErro::TipoInvalido,
+                "Mais de um tipo base na declaração".to_string(),
             ));
         }
+        especificadores.base = Some(base);
+        Ok(())
+    }
 
-        self.avancar();
-        while self.token_atual == Token::QuebraLinha {
-            self.avancar();
+    fn resolver_especificadores(especificadores: Especificadores<'a>) -> Option<Tipo<'a>> {
+        let Especificadores {
+            base,
+            shorts,
+            longs,
+            is_int,
+            sinal,
+            qualificadores,
+            definicao,
+            ..
+        } = especificadores;
+
+        let base = match (base, shorts, longs) {
+            (None, 0, 0) if is_int || sinal.is_some() => TipoBase::Int,
+            (None, 1, 0) => TipoBase::Short,
+            (None, 0, 1) => TipoBase::Long,
+            (None, 0, 2) => TipoBase::LongLong,
+            (Some(TipoBase::Double), 0, 1) if !is_int => TipoBase::LongDouble,
+            (Some(base), 0, 0) if !is_int => base,
+            _ => return None,
+        };
+
+        // signed/unsigned só se aplicam aos tipos inteiros
+        if sinal.is_some()
+            && !matches!(
+                base,
+                TipoBase::Char
+                    | TipoBase::Short
+                    | TipoBase::Int
+                    | TipoBase::Long
+                    | TipoBase::LongLong
+            )
+        {
+            return None;
         }
 
-        let corpo = self.parse_bloco();
+        Some(Tipo::Base {
+            base,
+            sinal,
+            qualificadores,
+            definicao,
+        })
+    }
+
+    fn parse_qualificadores(&mut self) -> Qualificadores {
+        let mut qualificadores = Qualificadores::default();
 
-        Stmt::DeclaracaoFuncao {
-            tipo_retorno,
-            nome,
-            parametros,
-            corpo: Box::new(corpo),
+        while let Token::PalavraChave(palavra) = self.token_atual {
+            match palavra {
+                PalavraChave::Const => qualificadores.constante = true,
+                PalavraChave::Volatile => qualificadores.volatil = true,
+                PalavraChave::Restrict => {}
+                _ => break,
+            }
+            self.avancar();
         }
+
+        qualificadores
     }
 
-    pub fn parse_declaracao_variavel(&mut self) -> Stmt {
-        let mut tipo: Vec<Token> = Vec::new();
+    // lê um declarador (com ou sem nome) e o aplica sobre o tipo base
+    fn parse_declarador(
+        &mut self,
+        base: Tipo<'a>,
+    ) -> Result<(Option<Token<'a>>, Tipo<'a>), Diagnostic> {
+        let (nome, derivacoes) = self.parse_derivacoes()?;
+
+        let tipo = derivacoes
+            .into_iter()
+            .fold(base, |tipo, derivacao| match derivacao {
+                Derivacao::Ponteiro(qualificadores) => Tipo::Ponteiro {
+                    apontado: Box::new(tipo),
+                    qualificadores,
+                },
+                Derivacao::Array(tamanho) => Tipo::Array {
+                    elemento: Box::new(tipo),
+                    tamanho: tamanho.map(Box::new),
+                },
+                Derivacao::Funcao(parametros, variadica) => Tipo::Funcao {
+                    retorno: Box::new(tipo),
+                    parametros,
+                    variadica,
+                },
+            });
+
+        Ok((nome, tipo))
+    }
 
-        while self.token_atual != Token::Fundo {
-            if let Token::Identificador(_) = self.token_atual {
-                if let Token::Igual | Token::PontoVirgula | Token::AbreColchete = self.espiadinha()
-                {
-                    break;
+    // em 'int *(*f)(int)[3]' os ponteiros da frente valem primeiro, depois os sufixos
+    // ([] e ()) de dentro para fora, e por último o declarador entre parênteses
+    fn parse_derivacoes(&mut self) -> Result<(Option<Token<'a>>, Vec<Derivacao<'a>>), Diagnostic> {
+        let mut derivacoes = Vec::new();
+
+        while self.token_atual == Token::Asterisco {
+            self.avancar();
+            derivacoes.push(Derivacao::Ponteiro(self.parse_qualificadores()));
+        }
+
+        let (nome, internas) =
+            if self.token_atual == Token::AbreParentesis && self.inicia_declarador_aninhado() {
+                self.avancar();
+                let internas = self.parse_derivacoes()?;
+                self.esperar(
+                    Token::FechaParentesis,
+                    CodigoErro::ParentesisEsperado,
+                    "Esperado ')' para fechar o declarador",
+                )?;
+                internas
+            } else if let Token::Identificador(_) = &self.token_atual {
+                let nome = self.token_atual.clone();
+                self.avancar();
+                (Some(nome), Vec::new())
+            } else {
+                (None, Vec::new())
+            };
+
+        let mut sufixos = Vec::new();
+
+        loop {
+            match self.token_atual {
+                Token::AbreColchete => {
+                    self.avancar();
+                    let tamanho = if self.token_atual == Token::FechaColchete {
+                        None
+                    } else {
+                        Some(self.parse_atribuicao()?)
+                    };
+                    self.esperar(
+                        Token::FechaColchete,
+                        CodigoErro::ColcheteEsperado,
+                        "Esperado ']' após tamanho do array",
+                    )?;
+                    sufixos.push(Derivacao::Array(tamanho));
+                }
+
+                Token::AbreParentesis => {
+                    self.avancar();
+                    let (parametros, variadica) = self.parse_parametros()?;
+                    sufixos.push(Derivacao::Funcao(parametros, variadica));
                 }
+
+                _ => break,
             }
-            tipo.push(self.token_atual.clone());
+        }
+
+        derivacoes.extend(sufixos.into_iter().rev());
+        derivacoes.extend(internas);
+
+        Ok((nome, derivacoes))
+    }
+
+    // depois de um '(' no declarador: '(*f)' é um declarador aninhado, '(int x)' são parâmetros
+    fn inicia_declarador_aninhado(&self) -> bool {
+        match self.espiadinha() {
+            Token::Asterisco | Token::AbreParentesis | Token::AbreColchete => true,
+            Token::Identificador(nome) => !self.typedefs.contains(nome.as_ref()),
+            _ => false,
+        }
+    }
+
+    // lista de parâmetros, já depois do '('; '(void)' é uma lista vazia
+    fn parse_parametros(&mut self) -> Result<(Vec<Parametro<'a>>, bool), Diagnostic> {
+        let mut parametros: Vec<Parametro> = Vec::new();
+        let mut variadica = false;
+
+        if self.token_atual == Token::PalavraChave(PalavraChave::Void)
+            && self.espiadinha() == Token::FechaParentesis
+        {
             self.avancar();
         }
 
-        if !matches!(self.token_atual, Token::Identificador(_)) {
-            self.erro(format!(
-                "Esperado nome de variável após o tipo, mas foi recebido {:?}",
-                self.token_atual
-            ));
+        if self.token_atual != Token::FechaParentesis {
+            loop {
+                if self.token_atual == Token::Reticencias {
+                    self.avancar();
+                    variadica = true;
+                    break;
+                }
+
+                let inicio_param = self.span_atual;
+                let (_, base) = self.parse_especificadores()?;
+                let (nome, tipo) = self.parse_declarador(base)?;
+                if let Some(Token::Identificador(nome)) = &nome {
+                    self.variaveis.insert(nome.to_string());
+                }
+
+                parametros.push(Parametro {
+                    tipo,
+                    nome,
+                    span: self.span_desde(inicio_param),
+                });
+
+                if self.token_atual == Token::Virgula {
+                    self.avancar();
+                } else {
+                    break;
+                }
+            }
         }
 
-        let nome = self.token_atual.clone();
+        self.esperar(
+            Token::FechaParentesis,
+            CodigoErro::ParentesisEsperado,
+            "Esperado ',' ou ')' após parâmetro de função",
+        )?;
+
+        Ok((parametros, variadica))
+    }
+
+    fn parse_campos(&mut self) -> Result<Vec<Campo<'a>>, Diagnostic> {
         self.avancar();
+        let mut campos: Vec<Campo> = Vec::new();
+
+        loop {
+            if self.token_atual == Token::FechaChave || self.token_atual == Token::Fundo {
+                break;
+            }
+            campos.extend(self.parse_campo()?);
+        }
 
-        let tamanho_array: Option<Expr>;
+        self.esperar(
+            Token::FechaChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '}' para fechar a definição",
+        )?;
+        Ok(campos)
+    }
 
-        if self.token_atual == Token::AbreColchete {
+    // 'int x, *y, z : 3;' gera um campo para cada declarador; uma struct ou union
+    // definida sem declarador vira um campo sem nome
+    fn parse_campo(&mut self) -> Result<Vec<Campo<'a>>, Diagnostic> {
+        let inicio_campo = self.span_atual;
+        let (_, base) = self.parse_especificadores()?;
+
+        if self.token_atual == Token::PontoVirgula
+            && let Tipo::Base {
+                definicao: Some(Definicao::Campos(_)),
+                ..
+            } = &base
+        {
             self.avancar();
-            if self.token_atual == Token::FechaColchete {
-                tamanho_array = Some(Expr::ArrayDim);
-            } else {
-                tamanho_array = Some(self.parse_atribuicao());
-            }
+            return Ok(vec![Campo {
+                tipo: base,
+                nome: None,
+                bits: None,
+                span: self.span_desde(inicio_campo),
+            }]);
+        }
 
-            if self.token_atual != Token::FechaColchete {
-                self.erro(format!(
-                    "Esperado ']' após tamanho do array, mas foi recebido {:?}",
-                    self.token_atual
+        let mut campos = Vec::new();
+
+        loop {
+            let inicio = self.span_atual;
+            let (nome, tipo) = self.parse_declarador(base.clone())?;
+
+            // só um campo de bits pode ficar sem nome ('int : 0;' alinha o próximo)
+            if nome.is_none() && self.token_atual != Token::DoisPontos {
+                return Err(self.erro(
+                    CodigoErro::NomeEsperado,
+                    format!(
+                        "Esperado nome do campo após o tipo, mas foi recebido {:?}",
+                        self.token_atual
+                    ),
                 ));
             }
+
+            let bits = if self.token_atual == Token::DoisPontos {
+                self.avancar();
+                Some(self.parse_ternario()?)
+            } else {
+                None
+            };
+
+            campos.push(Campo {
+                tipo,
+                nome,
+                bits,
+                span: self.span_desde(inicio),
+            });
+
+            if self.token_atual != Token::Virgula {
+                break;
+            }
             self.avancar();
-        } else {
-            tamanho_array = None;
         }
 
-        let inicializador: Option<Expr>;
+        self.esperar(
+            Token::PontoVirgula,
+            CodigoErro::PontoVirgulaEsperado,
+            "Esperado ';' após campo",
+        )?;
 
-        if self.token_atual == Token::Igual {
+        Ok(campos)
+    }
+
+    // '{ A, B = 2, C }'
+    fn parse_enumeradores(&mut self) -> Result<Vec<Enumerador<'a>>, Diagnostic> {
+        self.avancar();
+        let mut enumeradores: Vec<Enumerador> = Vec::new();
+
+        loop {
+            if self.token_atual == Token::FechaChave || self.token_atual == Token::Fundo {
+                break;
+            }
+
+            let inicio_enumerador = self.span_atual;
+            let nome_enumerador = if let Token::Identificador(nome) = &self.token_atual {
+                nome.clone()
+            } else {
+                return Err(self.erro(
+                    CodigoErro::NomeEsperado,
+                    format!(
+                        "Esperado nome do enumerador, mas foi recebido {:?}",
+                        self.token_atual
+                    ),
+                ));
+            };
             self.avancar();
-            inicializador = Some(self.parse_atribuicao());
-        } else {
-            inicializador = None;
-        }
 
-        if self.token_atual != Token::PontoVirgula {
-            self.erro(format!(
-                "Esperado ';' após declaração de variável, mas foi recebido {:?}",
-                self.token_atual
-            ));
+            let valor = if self.token_atual == Token::Igual {
+                self.avancar();
+                Some(self.parse_ternario()?)
+            } else {
+                None
+            };
+
+            enumeradores.push(Enumerador {
+                nome: nome_enumerador.into_owned(),
+                valor,
+                span: self.span_desde(inicio_enumerador),
+            });
+
+            // a vírgula depois do último enumerador é opcional
+            if self.token_atual == Token::Virgula {
+                self.avancar();
+            } else if self.token_atual != Token::FechaChave {
+                return Err(self.erro(
+                    CodigoErro::TokenInesperado,
+                    format!(
+                        "Esperado ',' ou '}}' após enumerador, mas foi recebido {:?}",
+                        self.token_atual
+                    ),
+                ));
+            }
         }
 
-        self.avancar();
+        self.esperar(
+            Token::FechaChave,
+            CodigoErro::ChaveEsperada,
+            "Esperado '}' para fechar o enum",
+        )?;
+        Ok(enumeradores)
+    }
 
-        Stmt::DeclaracaoVariavel {
-            tipo,
-            nome,
-            tamanho_array,
-            inicializador,
+    // depois de 'struct Nome { ... }' vem ';' ou as variáveis declaradas com esse tipo
+    fn parse_declaracao_apos_definicao(
+        &mut self,
+        inicio: Span,
+        armazenamento: Option<Armazenamento>,
+        base: Tipo<'a>,
+    ) -> Result<Option<Box<Stmt<'a>>>, Diagnostic> {
+        if self.token_atual == Token::PontoVirgula {
+            self.avancar();
+            return Ok(None);
         }
+
+        let declaracao = self.parse_resto_declaracao(inicio, armazenamento, base)?;
+        Ok(Some(Box::new(declaracao)))
     }
 
-    pub fn parse_declaracao_expressao(&mut self) -> Stmt {
-        let expr = self.parse_atribuicao();
+    pub fn parse_declaracao_expressao(&mut self) -> Result<Stmt<'a>, Diagnostic> {
+        let inicio = self.span_atual;
+        let expr = self.parse_virgula()?;
 
         if self.token_atual != Token::PontoVirgula {
-            self.erro(format!(
-                "Esperado ';' após expressão, mas foi recebido {:?}",
-                self.token_atual
+            return Err(self.erro(
+                CodigoErro::PontoVirgulaEsperado,
+                format!(
+                    "Esperado ';' após expressão, mas foi recebido {:?}",
+                    self.token_atual
+                ),
             ));
         }
 
         self.avancar();
-        Stmt::Expressao(expr)
+        Ok(Stmt::new(
+            StmtKind::Expressao(expr),
+            self.span_desde(inicio),
+        ))
+    }
+}
+
+// '(n)', '()', '(push)', '(push, n)' ou '(pop)', com n potência de 2 até 16
+fn interpretar_pack(argumentos: &[Token]) -> Result<(OperacaoPack, Option<u64>), String> {
+    let alinhamento = |token: &Token| match token {
+        Token::NumeroInt(numero) => numero
+            .valor_inteiro()
+            .filter(|n| n.is_power_of_two() && *n <= 16)
+            .ok_or_else(|| format!("Alinhamento inválido em '#pragma pack': {}", token)),
+        _ => Err(format!(
+            "Esperado alinhamento em '#pragma pack', recebido '{}'",
+            token
+        )),
+    };
+
+    let dentro = match argumentos {
+        [Token::AbreParentesis, dentro @ .., Token::FechaParentesis] => dentro,
+        _ => return Err("Esperado '(' ... ')' após '#pragma pack'".to_string()),
+    };
+
+    match dentro {
+        [] => Ok((OperacaoPack::Definir, None)),
+        [Token::Identificador(acao)] if acao == "push" => Ok((OperacaoPack::Empilhar, None)),
+        [Token::Identificador(acao)] if acao == "pop" => Ok((OperacaoPack::Desempilhar, None)),
+        [Token::Identificador(acao), Token::Virgula, n] if acao == "push" => {
+            Ok((OperacaoPack::Empilhar, Some(alinhamento(n)?)))
+        }
+        [n] => Ok((OperacaoPack::Definir, Some(alinhamento(n)?))),
+        _ => Err("Argumentos inválidos em '#pragma pack'".to_string()),
     }
 }
@@ -0,0 +1,1482 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::diagnostico::{CodigoErro, Diagnostic, Severidade};
+use crate::evaluator::{Valor, avaliar};
+use crate::inclusao::{SistemaArquivos, SistemaArquivosDisco, normalizar};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::{Dialeto, LiteralNumero, PalavraChave, Prefixo, Span, SpannedToken, Token};
+
+// token em processamento; ocultos guarda as macros que já foram expandidas para
+// produzi-lo e por isso não são expandidas de novo nele (evita recursão infinita)
+#[derive(Clone, Debug)]
+struct TokenPp<'a> {
+    token: Token<'a>,
+    span: Span,
+    // havia espaço antes do token na fonte; decide a grafia de '#x'
+    espaco_antes: bool,
+    ocultos: HashSet<String>,
+}
+
+impl<'a> TokenPp<'a> {
+    fn novo(token: Token<'a>, span: Span, espaco_antes: bool) -> Self {
+        Self {
+            token,
+            span,
+            espaco_antes,
+            ocultos: HashSet::new(),
+        }
+    }
+
+    fn into_owned(self) -> TokenPp<'static> {
+        TokenPp {
+            token: self.token.into_owned(),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Macro {
+    // None numa macro sem parênteses ('#define N 10')
+    parametros: Option<Vec<String>>,
+    variadica: bool,
+    // guardado sem empréstimos, porque a macro vive mais que a fonte que a definiu
+    corpo: Vec<TokenPp<'static>>,
+}
+
+impl Macro {
+    // redefinir uma macro com o mesmo corpo é permitido; só a grafia importa
+    fn mesma_definicao(&self, outra: &Macro) -> bool {
+        self.parametros == outra.parametros
+            && self.variadica == outra.variadica
+            && self.corpo.len() == outra.corpo.len()
+            && self
+                .corpo
+                .iter()
+                .zip(&outra.corpo)
+                .all(|(a, b)| a.token == b.token && a.espaco_antes == b.espaco_antes)
+    }
+
+    // posição do parâmetro nomeado pelo token; __VA_ARGS__ vem depois dos nomeados
+    fn indice_parametro(&self, token: &Token) -> Option<usize> {
+        let Token::Identificador(nome) = token else {
+            return None;
+        };
+        let parametros = self.parametros.as_ref()?;
+
+        if self.variadica && nome == "__VA_ARGS__" {
+            return Some(parametros.len());
+        }
+        parametros.iter().position(|parametro| parametro == nome)
+    }
+}
+
+// um '#if'/'#ifdef'/'#ifndef' ainda sem '#endif'
+struct Condicional {
+    // diretiva de abertura, para apontar a linha quando falta o '#endif'
+    span: Span,
+    pai_ativo: bool,
+    // o ramo atual é compilado
+    ativo: bool,
+    // algum ramo já foi compilado, então os '#elif' e '#else' seguintes são pulados
+    ramo_tomado: bool,
+    viu_else: bool,
+}
+
+// arquivo incluído cujos tokens ainda estão sendo processados
+struct ArquivoAberto {
+    caminho: PathBuf,
+    // quantos '#if' estavam abertos na inclusão; os que sobrarem no fim são do arquivo
+    condicionais: usize,
+}
+
+// etapa entre o Lexer e o Parser: executa '#define'/'#undef', descarta as regiões
+// desativadas por '#if', insere os arquivos de '#include' e expande as macros; as
+// demais diretivas seguem para o parser
+pub struct Preprocessador {
+    macros: HashMap<String, Macro>,
+    condicionais: Vec<Condicional>,
+    diagnosticos: Vec<Diagnostic>,
+
+    sistema_arquivos: Box<dyn SistemaArquivos>,
+    // '-I': procurados para '#include "..."' (depois da pasta do próprio arquivo) e '<...>'
+    caminhos_usuario: Vec<PathBuf>,
+    // '-isystem': procurados por último, nas duas formas
+    caminhos_sistema: Vec<PathBuf>,
+    // o índice de cada arquivo é o Span::arquivo dos seus tokens
+    arquivos: Vec<PathBuf>,
+    abertos: Vec<ArquivoAberto>,
+    // arquivos com '#pragma once' e arquivos protegidos por '#ifndef X #define X ... #endif'
+    incluir_uma_vez: HashSet<PathBuf>,
+    guardas: HashMap<PathBuf, String>,
+    // nomes dados por '#line "nome"', apontando para o arquivo de verdade
+    renomeados: HashMap<usize, usize>,
+    // as palavras-chave só são separadas dos identificadores na saída
+    dialeto: Dialeto,
+    // repassado ao Lexer de cada arquivo lido
+    trigrafos: bool,
+}
+
+impl Default for Preprocessador {
+    fn default() -> Self {
+        let mut preprocessador = Self {
+            macros: HashMap::new(),
+            condicionais: Vec::new(),
+            diagnosticos: Vec::new(),
+            sistema_arquivos: Box::new(SistemaArquivosDisco),
+            caminhos_usuario: Vec::new(),
+            caminhos_sistema: Vec::new(),
+            arquivos: vec![PathBuf::from("<entrada>")],
+            abertos: Vec::new(),
+            incluir_uma_vez: HashSet::new(),
+            guardas: HashMap::new(),
+            renomeados: HashMap::new(),
+            dialeto: Dialeto::C,
+            trigrafos: false,
+        };
+
+        // __DATE__ e __TIME__ ficam fixos durante todo o processamento
+        let (data, hora) = data_e_hora();
+        preprocessador.predefinir("__DATE__", &format!("\"{}\"", data));
+        preprocessador.predefinir("__TIME__", &format!("\"{}\"", hora));
+        preprocessador.predefinir("__STDC__", "1");
+        preprocessador.predefinir("__STDC_HOSTED__", "1");
+        preprocessador.definir_dialeto(Dialeto::C);
+
+        preprocessador
+    }
+}
+
+impl Preprocessador {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // em C++ __cplusplus substitui __STDC_VERSION__
+    pub fn definir_dialeto(&mut self, dialeto: Dialeto) {
+        self.dialeto = dialeto;
+        match dialeto {
+            Dialeto::C => {
+                self.macros.remove("__cplusplus");
+                self.predefinir("__STDC_VERSION__", "201710L");
+            }
+            Dialeto::Cpp => {
+                self.macros.remove("__STDC_VERSION__");
+                self.predefinir("__cplusplus", "201703L");
+            }
+        }
+    }
+
+    // equivalente a '-trigraphs': troca '??=' e os outros trígrafos nos arquivos lidos
+    pub fn definir_trigrafos(&mut self, ativos: bool) {
+        self.trigrafos = ativos;
+    }
+
+    // equivalente a '-D': "NDEBUG" define NDEBUG como 1, "N=10" define N como 10 e
+    // "F(x)=x*2" define uma macro com parâmetros
+    pub fn definir(&mut self, definicao: &str) -> Result<(), Diagnostic> {
+        let (cabecalho, corpo) = definicao.split_once('=').unwrap_or((definicao, "1"));
+        let arquivo = self.registrar_arquivo(PathBuf::from("<linha de comando>"));
+        let codigo = format!("#define {} {}\n", cabecalho, corpo);
+
+        let mut tokens = preparar(Lexer::new(&codigo).no_arquivo(arquivo).tokenizar()?);
+        let Some(diretiva) = tokens.pop_front() else {
+            unreachable!()
+        };
+        let (linha, _) = ler_linha(&mut tokens);
+
+        self.definir_macro(&diretiva, linha)
+    }
+
+    // equivalente a '-U'
+    pub fn remover_definicao(&mut self, nome: &str) {
+        self.macros.remove(nome);
+    }
+
+    pub fn usar_sistema_arquivos(&mut self, sistema_arquivos: Box<dyn SistemaArquivos>) {
+        self.sistema_arquivos = sistema_arquivos;
+    }
+
+    pub fn adicionar_caminho_usuario(&mut self, caminho: impl Into<PathBuf>) {
+        self.caminhos_usuario.push(caminho.into());
+    }
+
+    pub fn adicionar_caminho_sistema(&mut self, caminho: impl Into<PathBuf>) {
+        self.caminhos_sistema.push(caminho.into());
+    }
+
+    // caminho do arquivo dos tokens passados a processar; '#include "..."' procura
+    // primeiro na pasta dele
+    pub fn definir_arquivo_principal(&mut self, caminho: impl Into<PathBuf>) {
+        self.arquivos[0] = caminho.into();
+    }
+
+    // tabela para traduzir Span::arquivo no caminho do arquivo
+    pub fn arquivos(&self) -> &[PathBuf] {
+        &self.arquivos
+    }
+
+    // falha só quando há erros; avisos aparecem apenas em processar_parcial
+    pub fn processar<'a>(
+        &mut self,
+        tokens: Vec<SpannedToken<'a>>,
+    ) -> Result<Vec<SpannedToken<'a>>, Vec<Diagnostic>> {
+        let (tokens, diagnosticos) = self.processar_parcial(tokens);
+
+        if diagnosticos
+            .iter()
+            .any(|diagnostico| diagnostico.severidade == Severidade::Erro)
+        {
+            Err(diagnosticos)
+        } else {
+            Ok(tokens)
+        }
+    }
+
+    pub fn processar_parcial<'a>(
+        &mut self,
+        tokens: Vec<SpannedToken<'a>>,
+    ) -> (Vec<SpannedToken<'a>>, Vec<Diagnostic>) {
+        let mut entrada = preparar(tokens);
+        let mut saida = Vec::new();
+
+        while let Some(token) = entrada.pop_front() {
+            match &token.token {
+                Token::Diretiva(comando) => {
+                    let comando = comando.clone();
+                    self.executar_diretiva(token, &comando, &mut entrada, &mut saida);
+                }
+                // o Token::Fundo de um arquivo incluído só marca o fim dele
+                Token::Fundo if !self.abertos.is_empty() => self.fechar_inclusao(),
+                Token::InclusaoGlobal(_) | Token::InclusaoLocal(_) if self.regiao_ativa() => {
+                    self.incluir(token, &mut entrada, &mut saida);
+                }
+                _ if self.regiao_ativa() || token.token == Token::Fundo => {
+                    self.expandir_token(token, &mut entrada, &mut saida);
+                }
+                _ => {}
+            }
+        }
+
+        for condicional in std::mem::take(&mut self.condicionais) {
+            self.diagnosticos.push(nao_fechado(&condicional));
+        }
+
+        let saida = saida
+            .into_iter()
+            .map(|token| SpannedToken {
+                token: match token.token {
+                    Token::Identificador(nome) => {
+                        if let Some(operador) = Token::operador_alternativo(&nome, self.dialeto) {
+                            operador
+                        } else if let Some(palavra) = PalavraChave::buscar(&nome, self.dialeto) {
+                            Token::PalavraChave(palavra)
+                        } else {
+                            Token::Identificador(nome)
+                        }
+                    }
+                    outro => outro,
+                },
+                span: token.span,
+            })
+            .collect();
+
+        (saida, std::mem::take(&mut self.diagnosticos))
+    }
+
+    fn executar_diretiva<'a>(
+        &mut self,
+        diretiva: TokenPp<'a>,
+        comando: &str,
+        entrada: &mut VecDeque<TokenPp<'a>>,
+        saida: &mut Vec<TokenPp<'a>>,
+    ) {
+        let (linha, quebra) = ler_linha(entrada);
+
+        let resultado = match comando {
+            "if" | "ifdef" | "ifndef" => self.abrir_condicional(&diretiva, comando, &linha),
+            "elif" => self.trocar_ramo(&diretiva, Some(&linha)),
+            "else" => self.trocar_ramo(&diretiva, None),
+            "endif" => match self.condicionais.last() {
+                Some(topo) if self.mesmo_arquivo(topo.span, diretiva.span) => {
+                    self.condicionais.pop();
+                    Ok(())
+                }
+                _ => Err(sem_abertura(&diretiva, "endif")),
+            },
+
+            // numa região desativada as outras diretivas não têm efeito
+            _ if !self.regiao_ativa() => Ok(()),
+
+            "define" => self.definir_macro(&diretiva, linha),
+            "undef" => self.remover_macro(&diretiva, &linha),
+            "line" => self.remapear_linhas(&diretiva, linha, entrada),
+            "error" => {
+                // fatal: nada depois de '#error' é processado
+                let fundo = entrada.pop_back();
+                entrada.clear();
+                entrada.extend(fundo);
+                self.abertos.clear();
+                self.condicionais.clear();
+
+                Err(Diagnostic::erro(
+                    CodigoErro::DiretivaErro,
+                    format!("#error {}", texto_da_linha(&linha)),
+                    diretiva.span,
+                ))
+            }
+            "warning" => {
+                self.diagnosticos.push(Diagnostic::aviso(
+                    CodigoErro::DiretivaAviso,
+                    format!("#warning {}", texto_da_linha(&linha)),
+                    diretiva.span,
+                ));
+                Ok(())
+            }
+            // as demais viram Stmt::Diretiva no parser
+            _ => {
+                if comando == "pragma"
+                    && matches!(linha.first(), Some(TokenPp { token: Token::Identificador(nome), .. }) if nome == "once")
+                {
+                    let caminho = self.arquivos[self.arquivo_real(diretiva.span.arquivo)].clone();
+                    self.incluir_uma_vez.insert(caminho);
+                }
+
+                saida.push(diretiva);
+                saida.extend(linha);
+                saida.extend(quebra);
+                Ok(())
+            }
+        };
+
+        if let Err(diagnostico) = resultado {
+            self.diagnosticos.push(diagnostico);
+        }
+    }
+
+    // troca o token de '#include' pelos tokens do arquivo, que terminam no Token::Fundo
+    // dele; '<...>' que não é encontrado fica para o parser como Stmt::Inclusao, já
+    // que a biblioteca padrão normalmente não está disponível
+    fn incluir<'a>(
+        &mut self,
+        token: TokenPp<'a>,
+        entrada: &mut VecDeque<TokenPp<'a>>,
+        saida: &mut Vec<TokenPp<'a>>,
+    ) {
+        let (nome, global) = match &token.token {
+            Token::InclusaoGlobal(nome) => (nome.clone(), true),
+            Token::InclusaoLocal(nome) => (nome.clone(), false),
+            _ => unreachable!(),
+        };
+
+        let Some((caminho, conteudo)) = self.resolver_inclusao(&nome, global, token.span.arquivo)
+        else {
+            if global {
+                saida.push(token);
+            } else {
+                self.diagnosticos.push(Diagnostic::erro(
+                    CodigoErro::InclusaoNaoEncontrada,
+                    format!("Arquivo de inclusão '{}' não encontrado", nome),
+                    token.span,
+                ));
+            }
+            return;
+        };
+
+        if self.incluir_uma_vez.contains(&caminho) {
+            return;
+        }
+        if let Some(guarda) = self.guardas.get(&caminho)
+            && self.esta_definida(guarda)
+        {
+            return;
+        }
+
+        if self.abertos.iter().any(|aberto| aberto.caminho == caminho) {
+            let mut cadeia: Vec<String> = self
+                .abertos
+                .iter()
+                .skip_while(|aberto| aberto.caminho != caminho)
+                .map(|aberto| aberto.caminho.display().to_string())
+                .collect();
+            cadeia.push(caminho.display().to_string());
+
+            self.diagnosticos.push(Diagnostic::erro(
+                CodigoErro::InclusaoCiclica,
+                format!("Inclusão circular: {}", cadeia.join(" -> ")),
+                token.span,
+            ));
+            return;
+        }
+
+        let arquivo = self.registrar_arquivo(caminho.clone());
+        // os tokens não podem emprestar do conteúdo, que acaba aqui
+        let mut lexer = Lexer::new(&conteudo)
+            .no_arquivo(arquivo)
+            .com_trigrafos(self.trigrafos);
+        let tokens: Vec<SpannedToken> = match lexer.tokenizar() {
+            Ok(tokens) => tokens.into_iter().map(SpannedToken::into_owned).collect(),
+            Err(diagnostico) => {
+                self.diagnosticos.push(diagnostico);
+                return;
+            }
+        };
+
+        if let Some(guarda) = detectar_guarda(&tokens) {
+            self.guardas.insert(caminho.clone(), guarda);
+        }
+
+        self.abertos.push(ArquivoAberto {
+            caminho,
+            condicionais: self.condicionais.len(),
+        });
+        for incluido in preparar(tokens).into_iter().rev() {
+            entrada.push_front(incluido);
+        }
+    }
+
+    // índice do arquivo em self.arquivos, acrescentando-o na primeira vez; nomes
+    // dados por '#line' não contam, mesmo que coincidam com um arquivo de verdade
+    fn registrar_arquivo(&mut self, caminho: PathBuf) -> usize {
+        let existente = self
+            .arquivos
+            .iter()
+            .enumerate()
+            .position(|(i, arquivo)| *arquivo == caminho && !self.renomeados.contains_key(&i));
+
+        match existente {
+            Some(arquivo) => arquivo,
+            None => {
+                self.arquivos.push(caminho);
+                self.arquivos.len() - 1
+            }
+        }
+    }
+
+    fn arquivo_real(&self, arquivo: usize) -> usize {
+        self.renomeados.get(&arquivo).copied().unwrap_or(arquivo)
+    }
+
+    fn mesmo_arquivo(&self, a: Span, b: Span) -> bool {
+        self.arquivo_real(a.arquivo) == self.arquivo_real(b.arquivo)
+    }
+
+    // '#line N' e '#line N "nome"': o resto do arquivo passa a ser contado a partir de
+    // N na linha seguinte, e os spans apontam para 'nome'; os tokens ainda não
+    // processados do arquivo são reescritos aqui mesmo
+    fn remapear_linhas<'a>(
+        &mut self,
+        diretiva: &TokenPp<'a>,
+        linha: Vec<TokenPp<'a>>,
+        entrada: &mut VecDeque<TokenPp<'a>>,
+    ) -> Result<(), Diagnostic> {
+        let linha = self.expandir_argumento(linha);
+
+        let (numero, nome) = match linha.as_slice() {
+            [numero] => (numero, None),
+            [
+                numero,
+                TokenPp {
+                    token: Token::Texto(nome, Prefixo::Nenhum),
+                    ..
+                },
+            ] => (numero, Some(nome.clone())),
+            _ => {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DiretivaInvalida,
+                    "Esperado número de linha e nome de arquivo opcional após '#line'".to_string(),
+                    diretiva.span,
+                ));
+            }
+        };
+
+        let novo_numero = match &numero.token {
+            Token::NumeroInt(numero) if numero.texto.bytes().all(|c| c.is_ascii_digit()) => {
+                numero.texto.parse::<usize>().ok().filter(|&n| n > 0)
+            }
+            _ => None,
+        };
+        let Some(novo_numero) = novo_numero else {
+            return Err(Diagnostic::erro(
+                CodigoErro::DiretivaInvalida,
+                format!("Número de linha inválido em '#line': {}", numero.token),
+                numero.span,
+            ));
+        };
+
+        let arquivo = diretiva.span.arquivo;
+        let novo_arquivo = match nome {
+            Some(nome) => {
+                let real = self.arquivo_real(arquivo);
+                self.arquivos.push(PathBuf::from(nome.as_ref()));
+                let renomeado = self.arquivos.len() - 1;
+                self.renomeados.insert(renomeado, real);
+                renomeado
+            }
+            None => arquivo,
+        };
+
+        for token in entrada.iter_mut() {
+            if token.span.arquivo != arquivo {
+                continue;
+            }
+
+            token.span.linha =
+                novo_numero + token.span.linha.saturating_sub(diretiva.span.linha + 1);
+            token.span.arquivo = novo_arquivo;
+            if token.token == Token::Fundo {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fechar_inclusao(&mut self) {
+        let Some(aberto) = self.abertos.pop() else {
+            return;
+        };
+
+        while self.condicionais.len() > aberto.condicionais {
+            let condicional = self.condicionais.pop().expect("tamanho já verificado");
+            self.diagnosticos.push(nao_fechado(&condicional));
+        }
+    }
+
+    // '"..."' procura na pasta de quem inclui, depois em -I e por fim nos caminhos do
+    // sistema; '<...>' pula a primeira etapa
+    fn resolver_inclusao(
+        &self,
+        nome: &str,
+        global: bool,
+        arquivo_atual: usize,
+    ) -> Option<(PathBuf, String)> {
+        let pasta_atual = self.arquivos[self.arquivo_real(arquivo_atual)]
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        let pastas = (!global)
+            .then_some(&pasta_atual)
+            .into_iter()
+            .chain(&self.caminhos_usuario)
+            .chain(&self.caminhos_sistema);
+
+        for pasta in pastas {
+            let caminho = normalizar(&pasta.join(nome));
+            if let Some(conteudo) = self.sistema_arquivos.ler(&caminho) {
+                return Some((caminho, conteudo));
+            }
+        }
+
+        None
+    }
+
+    // macros predefinidas sem corpo fixo; o valor depende de onde aparecem
+    fn esta_definida(&self, nome: &str) -> bool {
+        self.macros.contains_key(nome) || matches!(nome, "__LINE__" | "__FILE__")
+    }
+
+    // macro predefinida comum, com o corpo lido de 'corpo'
+    fn predefinir(&mut self, nome: &str, corpo: &str) {
+        let tokens = Lexer::new(corpo)
+            .tokenizar()
+            .expect("corpo de macro predefinida é válido");
+        let mut corpo: Vec<TokenPp> = preparar(tokens)
+            .into_iter()
+            .map(TokenPp::into_owned)
+            .collect();
+        corpo.pop(); // Token::Fundo
+        if let Some(primeiro) = corpo.first_mut() {
+            primeiro.espaco_antes = false;
+        }
+
+        self.macros.insert(
+            nome.to_string(),
+            Macro {
+                parametros: None,
+                variadica: false,
+                corpo,
+            },
+        );
+    }
+
+    fn regiao_ativa(&self) -> bool {
+        self.condicionais
+            .last()
+            .is_none_or(|condicional| condicional.ativo)
+    }
+
+    // dentro de uma região desativada a condição nem é avaliada, mas o '#if' ainda
+    // precisa ser empilhado para casar com o seu '#endif'
+    fn abrir_condicional(
+        &mut self,
+        diretiva: &TokenPp,
+        comando: &str,
+        linha: &[TokenPp],
+    ) -> Result<(), Diagnostic> {
+        let pai_ativo = self.regiao_ativa();
+
+        let condicao = if !pai_ativo {
+            Ok(false)
+        } else if comando == "if" {
+            self.avaliar_condicao(diretiva, linha)
+        } else {
+            match linha {
+                [
+                    TokenPp {
+                        token: Token::Identificador(nome),
+                        ..
+                    },
+                ] => Ok(self.esta_definida(nome) == (comando == "ifdef")),
+                _ => Err(Diagnostic::erro(
+                    CodigoErro::DiretivaInvalida,
+                    format!("Esperado um único nome de macro após '#{}'", comando),
+                    diretiva.span,
+                )),
+            }
+        };
+
+        let ativo = pai_ativo && *condicao.as_ref().unwrap_or(&false);
+        self.condicionais.push(Condicional {
+            span: diretiva.span,
+            pai_ativo,
+            ativo,
+            ramo_tomado: ativo,
+            viu_else: false,
+        });
+
+        condicao.map(|_| ())
+    }
+
+    // '#elif' (com a linha da condição) ou '#else' (sem)
+    fn trocar_ramo(
+        &mut self,
+        diretiva: &TokenPp,
+        condicao: Option<&[TokenPp]>,
+    ) -> Result<(), Diagnostic> {
+        let comando = if condicao.is_some() { "elif" } else { "else" };
+
+        // um '#if' aberto em outro arquivo não pode ser continuado neste
+        let Some(topo) = self
+            .condicionais
+            .last()
+            .filter(|topo| self.mesmo_arquivo(topo.span, diretiva.span))
+        else {
+            return Err(sem_abertura(diretiva, comando));
+        };
+        if topo.viu_else {
+            return Err(Diagnostic::erro(
+                CodigoErro::CondicionalDesbalanceado,
+                format!("'#{}' depois do '#else' do mesmo '#if'", comando),
+                diretiva.span,
+            ));
+        }
+
+        let candidato = topo.pai_ativo && !topo.ramo_tomado;
+        let resultado = match condicao {
+            Some(linha) if candidato => self.avaliar_condicao(diretiva, linha),
+            _ => Ok(candidato),
+        };
+
+        let topo = self.condicionais.last_mut().expect("topo já verificado");
+        topo.ativo = candidato && *resultado.as_ref().unwrap_or(&false);
+        topo.ramo_tomado |= topo.ativo;
+        topo.viu_else = condicao.is_none();
+
+        resultado.map(|_| ())
+    }
+
+    // resolve 'defined', expande as macros, troca os nomes que sobram por 0 e avalia
+    // o resultado como expressão constante inteira
+    fn avaliar_condicao(
+        &mut self,
+        diretiva: &TokenPp,
+        linha: &[TokenPp],
+    ) -> Result<bool, Diagnostic> {
+        let Some(ultimo) = linha.last() else {
+            return Err(Diagnostic::erro(
+                CodigoErro::DiretivaInvalida,
+                format!("Esperada uma expressão após '{}'", diretiva.token),
+                diretiva.span,
+            ));
+        };
+
+        // 'defined X' e 'defined(X)' são resolvidos antes da expansão, senão X seria expandido
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < linha.len() {
+            let token = &linha[i];
+            if !matches!(&token.token, Token::Identificador(nome) if nome == "defined") {
+                tokens.push(token.clone());
+                i += 1;
+                continue;
+            }
+
+            let (nome, consumidos) = match (linha.get(i + 1), linha.get(i + 2), linha.get(i + 3)) {
+                (
+                    Some(TokenPp {
+                        token: Token::Identificador(nome),
+                        ..
+                    }),
+                    _,
+                    _,
+                ) => (nome, 2),
+                (
+                    Some(TokenPp {
+                        token: Token::AbreParentesis,
+                        ..
+                    }),
+                    Some(TokenPp {
+                        token: Token::Identificador(nome),
+                        ..
+                    }),
+                    Some(TokenPp {
+                        token: Token::FechaParentesis,
+                        ..
+                    }),
+                ) => (nome, 4),
+                _ => {
+                    return Err(Diagnostic::erro(
+                        CodigoErro::DiretivaInvalida,
+                        "Esperado nome de macro após 'defined'".to_string(),
+                        token.span,
+                    ));
+                }
+            };
+
+            let valor = if self.esta_definida(nome) { "1" } else { "0" };
+            tokens.push(TokenPp::novo(
+                Token::NumeroInt(LiteralNumero::decimal(valor.to_string())),
+                token.span,
+                token.espaco_antes,
+            ));
+            i += consumidos;
+        }
+
+        let mut expandidos: Vec<SpannedToken> = self
+            .expandir_argumento(tokens)
+            .into_iter()
+            .map(|token| SpannedToken {
+                token: match token.token {
+                    // em C++ 'true' é palavra-chave e vale 1 e 'and', 'not'... são operadores;
+                    // os outros nomes valem 0
+                    Token::Identificador(nome) => {
+                        match Token::operador_alternativo(&nome, self.dialeto) {
+                            Some(operador) => operador,
+                            None => {
+                                let valor = if self.dialeto == Dialeto::Cpp && nome == "true" {
+                                    "1"
+                                } else {
+                                    "0"
+                                };
+                                Token::NumeroInt(LiteralNumero::decimal(valor.to_string()))
+                            }
+                        }
+                    }
+                    outro => outro,
+                },
+                span: token.span,
+            })
+            .collect();
+        expandidos.push(SpannedToken {
+            token: Token::Fundo,
+            span: ultimo.span,
+        });
+
+        let expressao = Parser::new(expandidos).parse_expressao_isolada()?;
+        match avaliar(&expressao)? {
+            Valor::Int(valor) => Ok(valor != 0),
+            Valor::Unsigned(valor) => Ok(valor != 0),
+            Valor::Float(_) => Err(Diagnostic::erro(
+                CodigoErro::DiretivaInvalida,
+                format!("A condição de '{}' precisa ser inteira", diretiva.token),
+                expressao.span,
+            )),
+        }
+    }
+
+    fn definir_macro(&mut self, diretiva: &TokenPp, linha: Vec<TokenPp>) -> Result<(), Diagnostic> {
+        let mut tokens = linha.into_iter().peekable();
+
+        let (nome, span_nome) = match tokens.next() {
+            Some(TokenPp {
+                token: Token::Identificador(nome),
+                span,
+                ..
+            }) => (nome.into_owned(), span),
+            _ => {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DiretivaInvalida,
+                    "Esperado nome da macro após '#define'".to_string(),
+                    diretiva.span,
+                ));
+            }
+        };
+
+        if nome == "defined" {
+            return Err(Diagnostic::erro(
+                CodigoErro::DiretivaInvalida,
+                "'defined' não pode ser definido como macro".to_string(),
+                span_nome,
+            ));
+        }
+
+        // só um '(' colado ao nome abre a lista de parâmetros: '#define F (x)' não tem
+        let mut parametros = None;
+        let mut variadica = false;
+
+        if let Some(abre) = tokens.peek()
+            && abre.token == Token::AbreParentesis
+            && !abre.espaco_antes
+        {
+            tokens.next();
+            let mut lista: Vec<String> = Vec::new();
+
+            loop {
+                match tokens.next().map(|token| token.token) {
+                    Some(Token::FechaParentesis) if lista.is_empty() && !variadica => break,
+                    Some(Token::Identificador(parametro)) => {
+                        let parametro = parametro.into_owned();
+                        if lista.contains(&parametro) {
+                            return Err(Diagnostic::erro(
+                                CodigoErro::DiretivaInvalida,
+                                format!("Parâmetro '{}' repetido na macro '{}'", parametro, nome),
+                                span_nome,
+                            ));
+                        }
+                        lista.push(parametro);
+                    }
+                    Some(Token::Reticencias) => variadica = true,
+                    _ => {
+                        return Err(Diagnostic::erro(
+                            CodigoErro::DiretivaInvalida,
+                            format!("Esperado nome de parâmetro na macro '{}'", nome),
+                            span_nome,
+                        ));
+                    }
+                }
+
+                match tokens.next().map(|token| token.token) {
+                    Some(Token::FechaParentesis) => break,
+                    Some(Token::Virgula) if !variadica => {}
+                    _ => {
+                        return Err(Diagnostic::erro(
+                            CodigoErro::DiretivaInvalida,
+                            format!(
+                                "Esperado ',' ou ')' na lista de parâmetros da macro '{}'",
+                                nome
+                            ),
+                            span_nome,
+                        ));
+                    }
+                }
+            }
+
+            parametros = Some(lista);
+        }
+
+        let mut corpo: Vec<TokenPp> = tokens.map(TokenPp::into_owned).collect();
+        if let Some(primeiro) = corpo.first_mut() {
+            primeiro.espaco_antes = false;
+        }
+
+        let definicao = Macro {
+            parametros,
+            variadica,
+            corpo,
+        };
+        validar_corpo(&nome, &definicao, span_nome)?;
+
+        if let Some(anterior) = self.macros.get(&nome)
+            && !anterior.mesma_definicao(&definicao)
+        {
+            self.diagnosticos.push(Diagnostic::aviso(
+                CodigoErro::MacroRedefinida,
+                format!("Macro '{}' redefinida com outro corpo", nome),
+                span_nome,
+            ));
+        }
+
+        self.macros.insert(nome, definicao);
+        Ok(())
+    }
+
+    fn remover_macro(&mut self, diretiva: &TokenPp, linha: &[TokenPp]) -> Result<(), Diagnostic> {
+        match linha {
+            [
+                TokenPp {
+                    token: Token::Identificador(nome),
+                    ..
+                },
+            ] => {
+                self.macros.remove(nome.as_ref());
+                Ok(())
+            }
+            _ => Err(Diagnostic::erro(
+                CodigoErro::DiretivaInvalida,
+                "Esperado um único nome de macro após '#undef'".to_string(),
+                diretiva.span,
+            )),
+        }
+    }
+
+    // se o token nomeia uma macro, devolve a expansão ao começo da entrada para ser
+    // reexaminada junto com o que vem depois; senão o token vai direto para a saída
+    fn expandir_token<'a>(
+        &mut self,
+        token: TokenPp<'a>,
+        entrada: &mut VecDeque<TokenPp<'a>>,
+        saida: &mut Vec<TokenPp<'a>>,
+    ) {
+        // __LINE__ e __FILE__ viram a linha e o arquivo do próprio token; numa
+        // expansão, o token tem o span da invocação mais externa
+        if let Token::Identificador(nome) = &token.token
+            && !self.macros.contains_key(nome.as_ref())
+        {
+            let valor = match nome.as_ref() {
+                "__LINE__" => Some(Token::NumeroInt(LiteralNumero::decimal(
+                    token.span.linha.to_string(),
+                ))),
+                "__FILE__" => Some(Token::Texto(
+                    Cow::Owned(self.arquivos[token.span.arquivo].display().to_string()),
+                    Prefixo::Nenhum,
+                )),
+                _ => None,
+            };
+            if let Some(valor) = valor {
+                saida.push(TokenPp::novo(valor, token.span, token.espaco_antes));
+                return;
+            }
+        }
+
+        let definicao = match &token.token {
+            Token::Identificador(nome) if !token.ocultos.contains(nome.as_ref()) => {
+                self.macros.get(nome.as_ref()).cloned()
+            }
+            _ => None,
+        };
+        let Some(definicao) = definicao else {
+            saida.push(token);
+            return;
+        };
+        let Token::Identificador(nome) = &token.token else {
+            unreachable!()
+        };
+
+        let mut ocultos;
+        let argumentos;
+        let span;
+
+        if definicao.parametros.is_none() {
+            ocultos = token.ocultos.clone();
+            argumentos = Vec::new();
+            span = token.span;
+        } else {
+            // sem '(' logo depois, o nome de uma macro com parâmetros é só um nome
+            if !matches!(entrada.front(), Some(abre) if abre.token == Token::AbreParentesis) {
+                saida.push(token);
+                return;
+            }
+
+            let (coletados, fecha) = match self.coletar_argumentos(&token, &definicao, entrada) {
+                Ok(coletados) => coletados,
+                Err(diagnostico) => {
+                    self.diagnosticos.push(diagnostico);
+                    saida.push(token);
+                    return;
+                }
+            };
+
+            ocultos = token
+                .ocultos
+                .intersection(&fecha.ocultos)
+                .cloned()
+                .collect();
+            argumentos = coletados;
+            span = token.span.ate(fecha.span);
+        }
+        ocultos.insert(nome.to_string());
+
+        match self.substituir(&definicao, &argumentos, &ocultos, span) {
+            Ok(mut expansao) => {
+                if let Some(primeiro) = expansao.first_mut() {
+                    primeiro.espaco_antes = token.espaco_antes;
+                }
+                for expandido in expansao.into_iter().rev() {
+                    entrada.push_front(expandido);
+                }
+            }
+            Err(diagnostico) => self.diagnosticos.push(diagnostico),
+        }
+    }
+
+    // lê '(a, (b, c), d)' separando os argumentos pelas vírgulas de fora dos parênteses;
+    // numa macro variádica as vírgulas depois dos nomeados ficam em __VA_ARGS__
+    fn coletar_argumentos<'a>(
+        &self,
+        nome: &TokenPp<'a>,
+        definicao: &Macro,
+        entrada: &mut VecDeque<TokenPp<'a>>,
+    ) -> Result<(Vec<Vec<TokenPp<'a>>>, TokenPp<'a>), Diagnostic> {
+        let nomeados = definicao.parametros.as_ref().map_or(0, Vec::len);
+        let mut consumidos = vec![entrada.pop_front().expect("'(' já verificado")];
+        let mut argumentos: Vec<Vec<TokenPp>> = vec![Vec::new()];
+        let mut profundidade = 0;
+
+        let fecha = loop {
+            let token = match entrada.pop_front() {
+                Some(token) if !matches!(token.token, Token::Fundo | Token::Diretiva(_)) => token,
+                resto => {
+                    // devolve o que foi lido para que não se perca
+                    entrada.extend(resto);
+                    for token in consumidos.into_iter().rev() {
+                        entrada.push_front(token);
+                    }
+                    return Err(Diagnostic::erro(
+                        CodigoErro::ArgumentosMacro,
+                        format!(
+                            "Lista de argumentos da macro '{}' não foi fechada",
+                            nome.token
+                        ),
+                        nome.span,
+                    ));
+                }
+            };
+            consumidos.push(token.clone());
+
+            match token.token {
+                Token::AbreParentesis => profundidade += 1,
+                Token::FechaParentesis if profundidade == 0 => break token,
+                Token::FechaParentesis => profundidade -= 1,
+                Token::Virgula
+                    if profundidade == 0
+                        && (!definicao.variadica || argumentos.len() <= nomeados) =>
+                {
+                    argumentos.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+
+            argumentos
+                .last_mut()
+                .expect("sempre há um argumento aberto")
+                .push(token);
+        };
+
+        // 'F()' passa um argumento vazio, que numa macro sem parâmetros não conta
+        if nomeados == 0
+            && !definicao.variadica
+            && argumentos.len() == 1
+            && argumentos[0].is_empty()
+        {
+            argumentos.clear();
+        }
+        if definicao.variadica && argumentos.len() == nomeados {
+            argumentos.push(Vec::new());
+        }
+
+        let esperados = nomeados + usize::from(definicao.variadica);
+        if argumentos.len() != esperados {
+            return Err(Diagnostic::erro(
+                CodigoErro::ArgumentosMacro,
+                format!(
+                    "Macro '{}' espera {} argumento(s), mas recebeu {}",
+                    nome.token,
+                    nomeados,
+                    argumentos.len()
+                ),
+                nome.span.ate(fecha.span),
+            ));
+        }
+
+        Ok((argumentos, fecha))
+    }
+
+    // troca os parâmetros do corpo pelos argumentos e aplica '#' e '##'; argumentos
+    // vizinhos de '#' ou '##' entram sem expandir, os demais entram já expandidos
+    fn substituir<'a>(
+        &mut self,
+        definicao: &Macro,
+        argumentos: &[Vec<TokenPp<'a>>],
+        ocultos: &HashSet<String>,
+        span: Span,
+    ) -> Result<Vec<TokenPp<'a>>, Diagnostic> {
+        let corpo = &definicao.corpo;
+        // None é o marcador de um argumento vazio ao lado de '##'
+        let mut saida: Vec<Option<TokenPp>> = Vec::new();
+        let mut colar = false;
+        let mut i = 0;
+
+        while i < corpo.len() {
+            let token = &corpo[i];
+
+            if token.token == Token::CerquilhaDupla {
+                colar = true;
+                i += 1;
+                continue;
+            }
+
+            let mut item: Vec<Option<TokenPp>> = if token.token == Token::Cerquilha
+                && let Some(indice) = corpo
+                    .get(i + 1)
+                    .and_then(|proximo| definicao.indice_parametro(&proximo.token))
+            {
+                i += 1;
+                vec![Some(stringificar(&argumentos[indice], token, span))]
+            } else if let Some(indice) = definicao.indice_parametro(&token.token) {
+                let argumento = &argumentos[indice];
+                let vizinho_de_colagem = colar
+                    || corpo
+                        .get(i + 1)
+                        .is_some_and(|proximo| proximo.token == Token::CerquilhaDupla);
+
+                if !vizinho_de_colagem {
+                    self.expandir_argumento(argumento.clone())
+                        .into_iter()
+                        .map(Some)
+                        .collect()
+                } else if argumento.is_empty() {
+                    vec![None]
+                } else {
+                    argumento.iter().cloned().map(Some).collect()
+                }
+            } else {
+                let mut copia = token.clone();
+                copia.span = span;
+                vec![Some(copia)]
+            };
+            i += 1;
+
+            if colar {
+                colar = false;
+
+                // extensão do GCC: em ', ## __VA_ARGS__' a vírgula some quando não há
+                // argumentos variádicos, e não há colagem quando há
+                let e_va_args = definicao.variadica
+                    && matches!(&token.token, Token::Identificador(nome) if nome == "__VA_ARGS__");
+                if e_va_args
+                    && matches!(saida.last(), Some(Some(virgula)) if virgula.token == Token::Virgula)
+                {
+                    if matches!(item.as_slice(), [None]) {
+                        saida.pop();
+                    }
+                    saida.extend(item);
+                    continue;
+                }
+
+                let esquerda = saida.pop().flatten();
+                let direita = if item.is_empty() {
+                    None
+                } else {
+                    item.remove(0)
+                };
+                let colado = match (esquerda, direita) {
+                    (Some(esquerda), Some(direita)) => Some(colar_tokens(&esquerda, &direita)?),
+                    (esquerda, direita) => esquerda.or(direita),
+                };
+                saida.push(colado);
+            }
+
+            saida.extend(item);
+        }
+
+        Ok(saida
+            .into_iter()
+            .flatten()
+            .map(|mut token| {
+                token.ocultos.extend(ocultos.iter().cloned());
+                token
+            })
+            .collect())
+    }
+
+    // expansão completa de um argumento antes de entrar no corpo, isolada do resto
+    fn expandir_argumento<'a>(&mut self, argumento: Vec<TokenPp<'a>>) -> Vec<TokenPp<'a>> {
+        let mut entrada: VecDeque<TokenPp> = argumento.into();
+        let mut saida = Vec::new();
+
+        while let Some(token) = entrada.pop_front() {
+            self.expandir_token(token, &mut entrada, &mut saida);
+        }
+        saida
+    }
+}
+
+// data e hora atuais no formato de __DATE__ ("Mmm dd aaaa") e __TIME__ ("hh:mm:ss"),
+// em UTC já que a biblioteca padrão não conhece o fuso horário local
+fn data_e_hora() -> (String, String) {
+    const MESES: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let segundos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duracao| duracao.as_secs())
+        .unwrap_or(0);
+    let dias = (segundos / 86_400) as i64;
+    let segundos_do_dia = segundos % 86_400;
+
+    // dias desde 1970-01-01 para ano/mês/dia (algoritmo 'civil_from_days' de H. Hinnant)
+    let z = dias + 719_468;
+    let era = z.div_euclid(146_097);
+    let dia_da_era = z.rem_euclid(146_097);
+    let ano_da_era =
+        (dia_da_era - dia_da_era / 1460 + dia_da_era / 36_524 - dia_da_era / 146_096) / 365;
+    let dia_do_ano = dia_da_era - (365 * ano_da_era + ano_da_era / 4 - ano_da_era / 100);
+    let mp = (5 * dia_do_ano + 2) / 153;
+    let dia = dia_do_ano - (153 * mp + 2) / 5 + 1;
+    let mes = if mp < 10 { mp + 3 } else { mp - 9 };
+    let ano = ano_da_era + era * 400 + i64::from(mes <= 2);
+
+    let data = format!("{} {:>2} {}", MESES[(mes - 1) as usize], dia, ano);
+    let hora = format!(
+        "{:02}:{:02}:{:02}",
+        segundos_do_dia / 3600,
+        segundos_do_dia % 3600 / 60,
+        segundos_do_dia % 60
+    );
+
+    (data, hora)
+}
+
+fn nao_fechado(condicional: &Condicional) -> Diagnostic {
+    Diagnostic::erro(
+        CodigoErro::CondicionalDesbalanceado,
+        "Diretiva condicional sem '#endif' correspondente".to_string(),
+        condicional.span,
+    )
+}
+
+// 'X' quando o arquivo inteiro está dentro de '#ifndef X' / '#define X' ... '#endif';
+// incluir o arquivo de novo com X definida não produziria nada
+fn detectar_guarda(tokens: &[SpannedToken]) -> Option<String> {
+    let [
+        SpannedToken {
+            token: Token::Diretiva(ifndef),
+            ..
+        },
+        SpannedToken {
+            token: Token::Identificador(guarda),
+            ..
+        },
+        SpannedToken {
+            token: Token::QuebraLinha,
+            ..
+        },
+        SpannedToken {
+            token: Token::Diretiva(define),
+            ..
+        },
+        SpannedToken {
+            token: Token::Identificador(definida),
+            ..
+        },
+        ..,
+    ] = tokens
+    else {
+        return None;
+    };
+
+    if ifndef != "ifndef" || define != "define" || guarda != definida {
+        return None;
+    }
+
+    // o '#endif' que fecha o '#ifndef' precisa ser a última coisa do arquivo
+    let mut profundidade = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Diretiva(comando) = &token.token else {
+            continue;
+        };
+
+        match comando.as_ref() {
+            "if" | "ifdef" | "ifndef" => profundidade += 1,
+            "endif" => {
+                profundidade -= 1;
+                if profundidade == 0 {
+                    let resto = &tokens[i + 1..];
+                    let so_fim = resto
+                        .iter()
+                        .all(|token| matches!(token.token, Token::QuebraLinha | Token::Fundo));
+                    return so_fim.then(|| guarda.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn sem_abertura(diretiva: &TokenPp, comando: &str) -> Diagnostic {
+    Diagnostic::erro(
+        CodigoErro::CondicionalDesbalanceado,
+        format!("'#{}' sem '#if' correspondente", comando),
+        diretiva.span,
+    )
+}
+
+fn preparar<'a>(tokens: Vec<SpannedToken<'a>>) -> VecDeque<TokenPp<'a>> {
+    let mut fim_anterior: Option<usize> = None;
+
+    tokens
+        .into_iter()
+        .map(|SpannedToken { token, span }| {
+            let espaco_antes = fim_anterior.is_some_and(|fim| fim < span.inicio);
+            fim_anterior = Some(span.fim);
+            TokenPp::novo(como_identificador(token), span, espaco_antes)
+        })
+        .collect()
+}
+
+// no pré-processamento palavras-chave são nomes como os outros ('#define inline')
+fn como_identificador<'a>(token: Token<'a>) -> Token<'a> {
+    match token {
+        Token::PalavraChave(palavra) => Token::Identificador(Cow::Borrowed(palavra.como_str())),
+        outro => outro,
+    }
+}
+
+// tokens até o fim da diretiva, e a quebra de linha que a encerra (se não for o fim do arquivo)
+fn ler_linha<'a>(entrada: &mut VecDeque<TokenPp<'a>>) -> (Vec<TokenPp<'a>>, Option<TokenPp<'a>>) {
+    let mut linha = Vec::new();
+
+    while let Some(token) = entrada.pop_front() {
+        match token.token {
+            Token::QuebraLinha => return (linha, Some(token)),
+            Token::Fundo => {
+                entrada.push_front(token);
+                break;
+            }
+            _ => linha.push(token),
+        }
+    }
+
+    (linha, None)
+}
+
+fn validar_corpo(nome: &str, definicao: &Macro, span: Span) -> Result<(), Diagnostic> {
+    let corpo = &definicao.corpo;
+
+    let colagem_na_ponta = [corpo.first(), corpo.last()]
+        .into_iter()
+        .flatten()
+        .any(|token| token.token == Token::CerquilhaDupla);
+    if colagem_na_ponta {
+        return Err(Diagnostic::erro(
+            CodigoErro::DiretivaInvalida,
+            format!(
+                "'##' não pode aparecer no início nem no fim da macro '{}'",
+                nome
+            ),
+            span,
+        ));
+    }
+
+    // numa macro sem parênteses '#' é um token comum
+    if definicao.parametros.is_some() {
+        for (i, token) in corpo.iter().enumerate() {
+            let seguido_de_parametro = corpo
+                .get(i + 1)
+                .is_some_and(|proximo| definicao.indice_parametro(&proximo.token).is_some());
+
+            if token.token == Token::Cerquilha && !seguido_de_parametro {
+                return Err(Diagnostic::erro(
+                    CodigoErro::DiretivaInvalida,
+                    format!("'#' deve ser seguido de um parâmetro da macro '{}'", nome),
+                    span,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// mensagem de '#error' e '#warning'; o lexer já entrega o resto da linha num Texto
+fn texto_da_linha(linha: &[TokenPp]) -> String {
+    match linha {
+        [
+            TokenPp {
+                token: Token::Texto(mensagem, _),
+                ..
+            },
+        ] => mensagem.to_string(),
+        _ => linha
+            .iter()
+            .map(|token| token.token.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// '#x' com x = 'a + "b"' vira o literal "a + \"b\""
+fn stringificar(argumento: &[TokenPp], cerquilha: &TokenPp, span: Span) -> TokenPp<'static> {
+    let mut texto = String::new();
+
+    for (i, token) in argumento.iter().enumerate() {
+        if i > 0 && token.espaco_antes {
+            texto.push(' ');
+        }
+        texto.push_str(&token.token.to_string());
+    }
+
+    TokenPp::novo(
+        Token::Texto(Cow::Owned(texto), Prefixo::Nenhum),
+        span,
+        cerquilha.espaco_antes,
+    )
+}
+
+// aspa sem par não forma token; já um número malformado ('1e') ainda é um pp-number
+// e só dá erro se chegar ao parser
+fn literal_nao_fechado(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::ErroLexico(_, diagnostico)
+            if matches!(diagnostico.codigo, CodigoErro::CharNaoFechado | CodigoErro::StringNaoFechada)
+    )
+}
+
+// 'a ## b' junta as grafias e relê o resultado, que precisa ser um único token
+fn colar_tokens(esquerda: &TokenPp, direita: &TokenPp) -> Result<TokenPp<'static>, Diagnostic> {
+    let texto = format!("{}{}", esquerda.token, direita.token);
+
+    if let Ok(tokens) = Lexer::new(&texto).tokenizar()
+        && let [unico, fim] = tokens.as_slice()
+        && fim.token == Token::Fundo
+        && !matches!(
+            unico.token,
+            Token::Diretiva(_)
+                | Token::InclusaoGlobal(_)
+                | Token::InclusaoLocal(_)
+                | Token::Invalido
+        )
+        && !literal_nao_fechado(&unico.token)
+    {
+        return Ok(TokenPp {
+            token: como_identificador(unico.token.clone().into_owned()),
+            span: esquerda.span,
+            espaco_antes: esquerda.espaco_antes,
+            ocultos: esquerda
+                .ocultos
+                .intersection(&direita.ocultos)
+                .cloned()
+                .collect(),
+        });
+    }
+
+    Err(Diagnostic::erro(
+        CodigoErro::ColagemInvalida,
+        format!(
+            "Colar '{}' e '{}' não forma um token válido",
+            esquerda.token, direita.token
+        ),
+        esquerda.span,
+    ))
+}
@@ -0,0 +1,88 @@
+use crate::diagnostico::{CodigoErro, Diagnostic};
+use crate::parser::{Stmt, StmtKind};
+
+// onde uma declaração se encontra em relação aos laços e switches que a envolvem
+#[derive(Clone, Copy, Default)]
+struct Contexto {
+    dentro_laco: bool,
+    dentro_switch: bool,
+}
+
+// confere que 'break' só aparece dentro de laço ou switch e 'continue' só dentro de laço
+pub fn verificar_controle_fluxo(programa: &[Stmt]) -> Vec<Diagnostic> {
+    let mut diagnosticos = Vec::new();
+
+    for declaracao in programa {
+        visitar(declaracao, Contexto::default(), &mut diagnosticos);
+    }
+
+    diagnosticos
+}
+
+fn visitar(declaracao: &Stmt, contexto: Contexto, diagnosticos: &mut Vec<Diagnostic>) {
+    match &declaracao.kind {
+        StmtKind::Break if !contexto.dentro_laco && !contexto.dentro_switch => {
+            diagnosticos.push(Diagnostic::erro(
+                CodigoErro::BreakForaDeLaco,
+                "'break' fora de um laço ou 'switch'".to_string(),
+                declaracao.span,
+            ));
+        }
+
+        StmtKind::Continue if !contexto.dentro_laco => {
+            diagnosticos.push(Diagnostic::erro(
+                CodigoErro::ContinueForaDeLaco,
+                "'continue' fora de um laço".to_string(),
+                declaracao.span,
+            ));
+        }
+
+        StmtKind::While { corpo, .. }
+        | StmtKind::DoWhile { corpo, .. }
+        | StmtKind::For { corpo, .. } => {
+            let contexto_laco = Contexto {
+                dentro_laco: true,
+                ..contexto
+            };
+            visitar(corpo, contexto_laco, diagnosticos);
+        }
+
+        StmtKind::Switch { casos, .. } => {
+            let contexto_switch = Contexto {
+                dentro_switch: true,
+                ..contexto
+            };
+            for caso in casos {
+                for declaracao in &caso.declaracoes {
+                    visitar(declaracao, contexto_switch, diagnosticos);
+                }
+            }
+        }
+
+        StmtKind::If {
+            bloco_then,
+            bloco_else,
+            ..
+        } => {
+            visitar(bloco_then, contexto, diagnosticos);
+            if let Some(bloco_else) = bloco_else {
+                visitar(bloco_else, contexto, diagnosticos);
+            }
+        }
+
+        StmtKind::Bloco { declaracoes } => {
+            for declaracao in declaracoes {
+                visitar(declaracao, contexto, diagnosticos);
+            }
+        }
+
+        StmtKind::Rotulo { declaracao, .. } => visitar(declaracao, contexto, diagnosticos),
+
+        // o corpo de uma função começa fora de qualquer laço
+        StmtKind::DeclaracaoFuncao { corpo, .. } => {
+            visitar(corpo, Contexto::default(), diagnosticos);
+        }
+
+        _ => {}
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+use crate::parser::{Campo, Enumerador, Expr, Parametro};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Qualificadores {
+    pub constante: bool,
+    pub volatil: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Sinal {
+    Signed,
+    Unsigned,
+}
+
+// classe de armazenamento; vale para a entidade declarada, não para o tipo em si
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Armazenamento {
+    Typedef,
+    Extern,
+    Static,
+    Auto,
+    Register,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum TipoBase {
+    Void,
+    Char,
+    Short,
+    Int,
+    Long,
+    LongLong,
+    Float,
+    Double,
+    LongDouble,
+    Bool,
+    Struct(Option<String>),
+    Union(Option<String>),
+    Enum(Option<String>),
+    // nome definido por typedef (ou um tipo externo como FILE ou string)
+    Nomeado(String),
+}
+
+// corpo de 'struct { ... }' ou 'enum { ... }' escrito onde o tipo aparece, como no
+// membro 'union { int i; float f; };' ou em 'sizeof(struct { int a; })'
+#[derive(Clone, Debug, Serialize)]
+pub enum Definicao<'a> {
+    Campos(Vec<Campo<'a>>),
+    Enumeradores(Vec<Enumerador<'a>>),
+}
+
+// tipo de C montado pelo analisador de declaradores:
+// 'const unsigned long *p' vira Ponteiro { apontado: Base { Long, Unsigned, const } }
+#[derive(Clone, Debug, Serialize)]
+pub enum Tipo<'a> {
+    Base {
+        base: TipoBase,
+        sinal: Option<Sinal>,
+        qualificadores: Qualificadores,
+        // só quando a struct, union ou enum é definida no próprio especificador
+        definicao: Option<Definicao<'a>>,
+    },
+    Ponteiro {
+        apontado: Box<Tipo<'a>>,
+        qualificadores: Qualificadores,
+    },
+    Array {
+        elemento: Box<Tipo<'a>>,
+        tamanho: Option<Box<Expr<'a>>>,
+    },
+    Funcao {
+        retorno: Box<Tipo<'a>>,
+        parametros: Vec<Parametro<'a>>,
+        variadica: bool,
+    },
+}
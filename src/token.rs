@@ -1,7 +1,16 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
 use serde::Serialize;
 
+use crate::diagnostico::Diagnostic;
+
+// tokens lidos de uma fonte emprestam dela o texto de identificadores, números e
+// literais sem escapes; os criados pelo Preprocessador têm o texto próprio
 #[derive(Clone, PartialEq, Debug, Serialize)]
-pub enum Token {
+pub enum Token<'a> {
     // tipos de tokens
     Mais,
     Menos,
@@ -15,6 +24,8 @@ pub enum Token {
     AbreColchete,
     FechaColchete,
     Virgula,
+    DoisPontos,
+    Interrogacao,
 
     Incremento,
     Decremento,
@@ -31,6 +42,11 @@ pub enum Token {
     EComercialDuplo,
     BarraVertical,
     BarraVerticalDupla,
+    Circunflexo,
+    Til,
+    EComercialIgual,
+    BarraVerticalIgual,
+    CircunflexoIgual,
     Maior,
     Menor,
     MaiorOuIgual,
@@ -39,22 +55,693 @@ pub enum Token {
     Diferente,
     Negacao,
 
-    NumeroInt(String),
-    NumeroFloat(String),
-    Texto(String),
-    ConteudoChar(char),
-    Identificador(String),
+    NumeroInt(LiteralNumero<'a>),
+    NumeroFloat(LiteralNumero<'a>),
+    Texto(Cow<'a, str>, Prefixo),
+    ConteudoChar(char, Prefixo),
+    Identificador(Cow<'a, str>),
+    PalavraChave(PalavraChave),
 
-    InclusaoGlobal(String),
-    InclusaoLocal(String),
-    Diretiva(String),
+    InclusaoGlobal(Cow<'a, str>),
+    InclusaoLocal(Cow<'a, str>),
+    Diretiva(Cow<'a, str>),
+    // '#' e '##' no corpo de uma macro (stringificação e colagem)
+    Cerquilha,
+    CerquilhaDupla,
 
     Ponto,
+    Reticencias,
+    Seta,
     QuebraLinha,
 
     Invalido,
+    // literal com erro (número malformado, não fechado, escape inválido) e o texto dele
+    // como foi escrito; o erro vai junto e só é reportado se o token sair do
+    // pré-processador
+    ErroLexico(Cow<'a, str>, Box<Diagnostic>),
     Fundo,
 
     DeslocamentoEsq,
     DeslocamentoDir,
+    DeslocamentoEsqIgual,
+    DeslocamentoDirIgual,
+}
+
+impl Token<'_> {
+    // copia o texto emprestado, para o token sobreviver à fonte
+    pub fn into_owned(self) -> Token<'static> {
+        let dono = |texto: Cow<'_, str>| Cow::Owned(texto.into_owned());
+
+        match self {
+            Token::NumeroInt(numero) => Token::NumeroInt(numero.into_owned()),
+            Token::NumeroFloat(numero) => Token::NumeroFloat(numero.into_owned()),
+            Token::Texto(texto, prefixo) => Token::Texto(dono(texto), prefixo),
+            Token::ConteudoChar(c, prefixo) => Token::ConteudoChar(c, prefixo),
+            Token::Identificador(nome) => Token::Identificador(dono(nome)),
+            Token::PalavraChave(palavra) => Token::PalavraChave(palavra),
+            Token::InclusaoGlobal(path) => Token::InclusaoGlobal(dono(path)),
+            Token::InclusaoLocal(path) => Token::InclusaoLocal(dono(path)),
+            Token::Diretiva(comando) => Token::Diretiva(dono(comando)),
+
+            Token::Mais => Token::Mais,
+            Token::Menos => Token::Menos,
+            Token::Igual => Token::Igual,
+            Token::PontoVirgula => Token::PontoVirgula,
+            Token::AbreParentesis => Token::AbreParentesis,
+            Token::FechaParentesis => Token::FechaParentesis,
+            Token::AbreChave => Token::AbreChave,
+            Token::FechaChave => Token::FechaChave,
+            Token::AbreColchete => Token::AbreColchete,
+            Token::FechaColchete => Token::FechaColchete,
+            Token::Virgula => Token::Virgula,
+            Token::DoisPontos => Token::DoisPontos,
+            Token::Interrogacao => Token::Interrogacao,
+            Token::Incremento => Token::Incremento,
+            Token::Decremento => Token::Decremento,
+            Token::SomaIgual => Token::SomaIgual,
+            Token::SubtracaoIgual => Token::SubtracaoIgual,
+            Token::MultiplicacaoIgual => Token::MultiplicacaoIgual,
+            Token::DivisaoIgual => Token::DivisaoIgual,
+            Token::ModuloIgual => Token::ModuloIgual,
+            Token::Asterisco => Token::Asterisco,
+            Token::Divisao => Token::Divisao,
+            Token::Modulo => Token::Modulo,
+            Token::EComercial => Token::EComercial,
+            Token::EComercialDuplo => Token::EComercialDuplo,
+            Token::BarraVertical => Token::BarraVertical,
+            Token::BarraVerticalDupla => Token::BarraVerticalDupla,
+            Token::Circunflexo => Token::Circunflexo,
+            Token::Til => Token::Til,
+            Token::EComercialIgual => Token::EComercialIgual,
+            Token::BarraVerticalIgual => Token::BarraVerticalIgual,
+            Token::CircunflexoIgual => Token::CircunflexoIgual,
+            Token::Maior => Token::Maior,
+            Token::Menor => Token::Menor,
+            Token::MaiorOuIgual => Token::MaiorOuIgual,
+            Token::Comparar => Token::Comparar,
+            Token::MenorOuIgual => Token::MenorOuIgual,
+            Token::Diferente => Token::Diferente,
+            Token::Negacao => Token::Negacao,
+            Token::Cerquilha => Token::Cerquilha,
+            Token::CerquilhaDupla => Token::CerquilhaDupla,
+            Token::Ponto => Token::Ponto,
+            Token::Reticencias => Token::Reticencias,
+            Token::Seta => Token::Seta,
+            Token::QuebraLinha => Token::QuebraLinha,
+            Token::Invalido => Token::Invalido,
+            Token::ErroLexico(texto, diagnostico) => Token::ErroLexico(dono(texto), diagnostico),
+            Token::Fundo => Token::Fundo,
+            Token::DeslocamentoEsq => Token::DeslocamentoEsq,
+            Token::DeslocamentoDir => Token::DeslocamentoDir,
+            Token::DeslocamentoEsqIgual => Token::DeslocamentoEsqIgual,
+            Token::DeslocamentoDirIgual => Token::DeslocamentoDirIgual,
+        }
+    }
+
+    // 'and', 'not_eq' e as outras grafias alternativas do C++ não são palavras-chave:
+    // são o próprio operador escrito de outro jeito. em C são nomes comuns (<iso646.h>
+    // as define como macros)
+    pub fn operador_alternativo(nome: &str, dialeto: Dialeto) -> Option<Token<'static>> {
+        if dialeto != Dialeto::Cpp {
+            return None;
+        }
+
+        Some(match nome {
+            "and" => Token::EComercialDuplo,
+            "and_eq" => Token::EComercialIgual,
+            "bitand" => Token::EComercial,
+            "bitor" => Token::BarraVertical,
+            "compl" => Token::Til,
+            "not" => Token::Negacao,
+            "not_eq" => Token::Diferente,
+            "or" => Token::BarraVerticalDupla,
+            "or_eq" => Token::BarraVerticalIgual,
+            "xor" => Token::Circunflexo,
+            "xor_eq" => Token::CircunflexoIgual,
+            _ => return None,
+        })
+    }
+}
+
+// grafia do token como apareceria na fonte; usada na stringificação e na colagem
+// de macros, onde o texto precisa ser reconstruído
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let simbolo = match self {
+            Token::Mais => "+",
+            Token::Menos => "-",
+            Token::Igual => "=",
+            Token::PontoVirgula => ";",
+            Token::AbreParentesis => "(",
+            Token::FechaParentesis => ")",
+            Token::AbreChave => "{",
+            Token::FechaChave => "}",
+            Token::AbreColchete => "[",
+            Token::FechaColchete => "]",
+            Token::Virgula => ",",
+            Token::DoisPontos => ":",
+            Token::Interrogacao => "?",
+            Token::Incremento => "++",
+            Token::Decremento => "--",
+            Token::SomaIgual => "+=",
+            Token::SubtracaoIgual => "-=",
+            Token::MultiplicacaoIgual => "*=",
+            Token::DivisaoIgual => "/=",
+            Token::ModuloIgual => "%=",
+            Token::Asterisco => "*",
+            Token::Divisao => "/",
+            Token::Modulo => "%",
+            Token::EComercial => "&",
+            Token::EComercialDuplo => "&&",
+            Token::BarraVertical => "|",
+            Token::BarraVerticalDupla => "||",
+            Token::Circunflexo => "^",
+            Token::Til => "~",
+            Token::EComercialIgual => "&=",
+            Token::BarraVerticalIgual => "|=",
+            Token::CircunflexoIgual => "^=",
+            Token::Maior => ">",
+            Token::Menor => "<",
+            Token::MaiorOuIgual => ">=",
+            Token::Comparar => "==",
+            Token::MenorOuIgual => "<=",
+            Token::Diferente => "!=",
+            Token::Negacao => "!",
+            Token::Ponto => ".",
+            Token::Reticencias => "...",
+            Token::Seta => "->",
+            Token::Cerquilha => "#",
+            Token::CerquilhaDupla => "##",
+            Token::DeslocamentoEsq => "<<",
+            Token::DeslocamentoDir => ">>",
+            Token::DeslocamentoEsqIgual => "<<=",
+            Token::DeslocamentoDirIgual => ">>=",
+            Token::QuebraLinha => "\n",
+            Token::Invalido | Token::Fundo => "",
+            Token::ErroLexico(texto, _) => return f.write_str(texto),
+
+            Token::NumeroInt(numero) | Token::NumeroFloat(numero) => {
+                return f.write_str(&numero.texto);
+            }
+            Token::Identificador(texto) => return f.write_str(texto),
+            Token::PalavraChave(palavra) => palavra.como_str(),
+            Token::Texto(texto, prefixo) => {
+                return write!(f, "{}\"{}\"", prefixo.como_str(), escapar(texto, '"'));
+            }
+            Token::ConteudoChar(c, prefixo) => {
+                return write!(
+                    f,
+                    "{}'{}'",
+                    prefixo.como_str(),
+                    escapar(&c.to_string(), '\'')
+                );
+            }
+            Token::InclusaoGlobal(path) => return write!(f, "#include <{}>", path),
+            Token::InclusaoLocal(path) => return write!(f, "#include \"{}\"", path),
+            Token::Diretiva(comando) => return write!(f, "#{}", comando),
+        };
+
+        f.write_str(simbolo)
+    }
+}
+
+// linguagem do código fonte; decide as palavras-chave do Lexer e as macros
+// predefinidas do Preprocessador
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialeto {
+    #[default]
+    C,
+    Cpp,
+}
+
+// palavras reservadas de C17 e de C++17; as de C11 com '_' mantêm a grafia
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum PalavraChave {
+    // C e C++
+    Auto,
+    Break,
+    Case,
+    Char,
+    Const,
+    Continue,
+    Default,
+    Do,
+    Double,
+    Else,
+    Enum,
+    Extern,
+    Float,
+    For,
+    Goto,
+    If,
+    Inline,
+    Int,
+    Long,
+    Register,
+    Return,
+    Short,
+    Signed,
+    Sizeof,
+    Static,
+    Struct,
+    Switch,
+    Typedef,
+    Union,
+    Unsigned,
+    Void,
+    Volatile,
+    While,
+
+    // só C
+    Restrict,
+    _Alignas,
+    _Alignof,
+    _Atomic,
+    _Bool,
+    _Complex,
+    _Generic,
+    _Imaginary,
+    _Noreturn,
+    _StaticAssert,
+    _ThreadLocal,
+
+    // só C++
+    Alignas,
+    Alignof,
+    Asm,
+    Bool,
+    Catch,
+    Char16T,
+    Char32T,
+    Class,
+    Constexpr,
+    ConstCast,
+    Decltype,
+    Delete,
+    DynamicCast,
+    Explicit,
+    Export,
+    False,
+    Friend,
+    Mutable,
+    Namespace,
+    New,
+    Noexcept,
+    Nullptr,
+    Operator,
+    Private,
+    Protected,
+    Public,
+    ReinterpretCast,
+    StaticAssert,
+    StaticCast,
+    Template,
+    This,
+    ThreadLocal,
+    Throw,
+    True,
+    Try,
+    Typeid,
+    Typename,
+    Using,
+    Virtual,
+    WcharT,
+}
+
+// em quais dialetos cada grafia é reservada
+#[derive(PartialEq)]
+enum Reservada {
+    Ambos,
+    SoC,
+    SoCpp,
+}
+
+const PALAVRAS_CHAVE: &[(&str, PalavraChave, Reservada)] = &[
+    ("auto", PalavraChave::Auto, Reservada::Ambos),
+    ("break", PalavraChave::Break, Reservada::Ambos),
+    ("case", PalavraChave::Case, Reservada::Ambos),
+    ("char", PalavraChave::Char, Reservada::Ambos),
+    ("const", PalavraChave::Const, Reservada::Ambos),
+    ("continue", PalavraChave::Continue, Reservada::Ambos),
+    ("default", PalavraChave::Default, Reservada::Ambos),
+    ("do", PalavraChave::Do, Reservada::Ambos),
+    ("double", PalavraChave::Double, Reservada::Ambos),
+    ("else", PalavraChave::Else, Reservada::Ambos),
+    ("enum", PalavraChave::Enum, Reservada::Ambos),
+    ("extern", PalavraChave::Extern, Reservada::Ambos),
+    ("float", PalavraChave::Float, Reservada::Ambos),
+    ("for", PalavraChave::For, Reservada::Ambos),
+    ("goto", PalavraChave::Goto, Reservada::Ambos),
+    ("if", PalavraChave::If, Reservada::Ambos),
+    ("inline", PalavraChave::Inline, Reservada::Ambos),
+    ("int", PalavraChave::Int, Reservada::Ambos),
+    ("long", PalavraChave::Long, Reservada::Ambos),
+    ("register", PalavraChave::Register, Reservada::Ambos),
+    ("return", PalavraChave::Return, Reservada::Ambos),
+    ("short", PalavraChave::Short, Reservada::Ambos),
+    ("signed", PalavraChave::Signed, Reservada::Ambos),
+    ("sizeof", PalavraChave::Sizeof, Reservada::Ambos),
+    ("static", PalavraChave::Static, Reservada::Ambos),
+    ("struct", PalavraChave::Struct, Reservada::Ambos),
+    ("switch", PalavraChave::Switch, Reservada::Ambos),
+    ("typedef", PalavraChave::Typedef, Reservada::Ambos),
+    ("union", PalavraChave::Union, Reservada::Ambos),
+    ("unsigned", PalavraChave::Unsigned, Reservada::Ambos),
+    ("void", PalavraChave::Void, Reservada::Ambos),
+    ("volatile", PalavraChave::Volatile, Reservada::Ambos),
+    ("while", PalavraChave::While, Reservada::Ambos),
+    ("restrict", PalavraChave::Restrict, Reservada::SoC),
+    ("_Alignas", PalavraChave::_Alignas, Reservada::SoC),
+    ("_Alignof", PalavraChave::_Alignof, Reservada::SoC),
+    ("_Atomic", PalavraChave::_Atomic, Reservada::SoC),
+    ("_Bool", PalavraChave::_Bool, Reservada::SoC),
+    ("_Complex", PalavraChave::_Complex, Reservada::SoC),
+    ("_Generic", PalavraChave::_Generic, Reservada::SoC),
+    ("_Imaginary", PalavraChave::_Imaginary, Reservada::SoC),
+    ("_Noreturn", PalavraChave::_Noreturn, Reservada::SoC),
+    (
+        "_Static_assert",
+        PalavraChave::_StaticAssert,
+        Reservada::SoC,
+    ),
+    ("_Thread_local", PalavraChave::_ThreadLocal, Reservada::SoC),
+    ("alignas", PalavraChave::Alignas, Reservada::SoCpp),
+    ("alignof", PalavraChave::Alignof, Reservada::SoCpp),
+    ("asm", PalavraChave::Asm, Reservada::SoCpp),
+    ("bool", PalavraChave::Bool, Reservada::SoCpp),
+    ("catch", PalavraChave::Catch, Reservada::SoCpp),
+    ("char16_t", PalavraChave::Char16T, Reservada::SoCpp),
+    ("char32_t", PalavraChave::Char32T, Reservada::SoCpp),
+    ("class", PalavraChave::Class, Reservada::SoCpp),
+    ("constexpr", PalavraChave::Constexpr, Reservada::SoCpp),
+    ("const_cast", PalavraChave::ConstCast, Reservada::SoCpp),
+    ("decltype", PalavraChave::Decltype, Reservada::SoCpp),
+    ("delete", PalavraChave::Delete, Reservada::SoCpp),
+    ("dynamic_cast", PalavraChave::DynamicCast, Reservada::SoCpp),
+    ("explicit", PalavraChave::Explicit, Reservada::SoCpp),
+    ("export", PalavraChave::Export, Reservada::SoCpp),
+    ("false", PalavraChave::False, Reservada::SoCpp),
+    ("friend", PalavraChave::Friend, Reservada::SoCpp),
+    ("mutable", PalavraChave::Mutable, Reservada::SoCpp),
+    ("namespace", PalavraChave::Namespace, Reservada::SoCpp),
+    ("new", PalavraChave::New, Reservada::SoCpp),
+    ("noexcept", PalavraChave::Noexcept, Reservada::SoCpp),
+    ("nullptr", PalavraChave::Nullptr, Reservada::SoCpp),
+    ("operator", PalavraChave::Operator, Reservada::SoCpp),
+    ("private", PalavraChave::Private, Reservada::SoCpp),
+    ("protected", PalavraChave::Protected, Reservada::SoCpp),
+    ("public", PalavraChave::Public, Reservada::SoCpp),
+    (
+        "reinterpret_cast",
+        PalavraChave::ReinterpretCast,
+        Reservada::SoCpp,
+    ),
+    (
+        "static_assert",
+        PalavraChave::StaticAssert,
+        Reservada::SoCpp,
+    ),
+    ("static_cast", PalavraChave::StaticCast, Reservada::SoCpp),
+    ("template", PalavraChave::Template, Reservada::SoCpp),
+    ("this", PalavraChave::This, Reservada::SoCpp),
+    ("thread_local", PalavraChave::ThreadLocal, Reservada::SoCpp),
+    ("throw", PalavraChave::Throw, Reservada::SoCpp),
+    ("true", PalavraChave::True, Reservada::SoCpp),
+    ("try", PalavraChave::Try, Reservada::SoCpp),
+    ("typeid", PalavraChave::Typeid, Reservada::SoCpp),
+    ("typename", PalavraChave::Typename, Reservada::SoCpp),
+    ("using", PalavraChave::Using, Reservada::SoCpp),
+    ("virtual", PalavraChave::Virtual, Reservada::SoCpp),
+    ("wchar_t", PalavraChave::WcharT, Reservada::SoCpp),
+];
+
+impl PalavraChave {
+    // a palavra-chave com essa grafia no dialeto, se houver; o lexer consulta a cada
+    // identificador, então a tabela é indexada pela grafia na primeira busca
+    pub fn buscar(nome: &str, dialeto: Dialeto) -> Option<PalavraChave> {
+        static INDICE: OnceLock<HashMap<&str, usize>> = OnceLock::new();
+
+        let indice = INDICE.get_or_init(|| {
+            PALAVRAS_CHAVE
+                .iter()
+                .enumerate()
+                .map(|(i, (grafia, _, _))| (*grafia, i))
+                .collect()
+        });
+        let (_, palavra, reservada) = &PALAVRAS_CHAVE[*indice.get(nome)?];
+
+        let reservada_no_dialeto = match dialeto {
+            Dialeto::C => *reservada != Reservada::SoCpp,
+            Dialeto::Cpp => *reservada != Reservada::SoC,
+        };
+        reservada_no_dialeto.then_some(*palavra)
+    }
+
+    pub fn como_str(self) -> &'static str {
+        PALAVRAS_CHAVE
+            .iter()
+            .find(|(_, palavra, _)| *palavra == self)
+            .map(|(grafia, _, _)| *grafia)
+            .expect("toda palavra-chave está na tabela")
+    }
+}
+
+// codificação pedida pelo prefixo de um literal de texto ou de caractere
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize)]
+pub enum Prefixo {
+    #[default]
+    Nenhum,
+    // L"..."
+    Largo,
+    // u8"..."
+    Utf8,
+    // u"..."
+    Utf16,
+    // U"..."
+    Utf32,
+}
+
+impl Prefixo {
+    // maior valor de uma unidade de código; limita os escapes octais e '\x'
+    pub fn unidade_maxima(self) -> u32 {
+        match self {
+            Prefixo::Nenhum | Prefixo::Utf8 => 0xFF,
+            Prefixo::Utf16 => 0xFFFF,
+            Prefixo::Largo | Prefixo::Utf32 => u32::MAX,
+        }
+    }
+
+    pub fn como_str(self) -> &'static str {
+        match self {
+            Prefixo::Nenhum => "",
+            Prefixo::Largo => "L",
+            Prefixo::Utf8 => "u8",
+            Prefixo::Utf16 => "u",
+            Prefixo::Utf32 => "U",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum Base {
+    Decimal,
+    Hexadecimal,
+    Octal,
+    Binaria,
+}
+
+impl Base {
+    pub fn radix(self) -> u32 {
+        match self {
+            Base::Decimal => 10,
+            Base::Hexadecimal => 16,
+            Base::Octal => 8,
+            Base::Binaria => 2,
+        }
+    }
+
+    fn prefixo(self) -> usize {
+        match self {
+            Base::Hexadecimal | Base::Binaria => 2,
+            Base::Decimal | Base::Octal => 0,
+        }
+    }
+}
+
+// literal numérico como foi escrito ('0x1Fu', '1.5e-3f'); o valor só é calculado
+// pelo parser, que reporta os que não cabem no tipo
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct LiteralNumero<'a> {
+    pub texto: Cow<'a, str>,
+    pub base: Base,
+    // 'u', 'l', 'll' e combinações nos inteiros; 'f' ou 'l' nos de ponto flutuante
+    pub sufixo: Cow<'a, str>,
+}
+
+impl<'a> LiteralNumero<'a> {
+    pub fn decimal(texto: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            texto: texto.into(),
+            base: Base::Decimal,
+            sufixo: Cow::Borrowed(""),
+        }
+    }
+
+    pub fn into_owned(self) -> LiteralNumero<'static> {
+        LiteralNumero {
+            texto: Cow::Owned(self.texto.into_owned()),
+            base: self.base,
+            sufixo: Cow::Owned(self.sufixo.into_owned()),
+        }
+    }
+
+    // o texto sem o prefixo da base ('0x', '0b') e sem o sufixo
+    pub fn digitos(&self) -> &str {
+        &self.texto[self.base.prefixo()..self.texto.len() - self.sufixo.len()]
+    }
+
+    // None quando o valor não cabe em 64 bits
+    pub fn valor_inteiro(&self) -> Option<u64> {
+        u64::from_str_radix(self.digitos(), self.base.radix()).ok()
+    }
+
+    pub fn valor_float(&self) -> f64 {
+        let digitos = self.digitos();
+        if self.base != Base::Hexadecimal {
+            return digitos.parse().unwrap_or(f64::NAN);
+        }
+
+        // '1.8p3' = 0x18 * 2^(3 - 4 * dígitos da fração)
+        let (mantissa, expoente) = digitos.split_once(['p', 'P']).unwrap_or((digitos, "0"));
+        let (inteira, fracao) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let mut valor = 0.0;
+        for c in inteira.chars().chain(fracao.chars()) {
+            valor = valor * 16.0 + f64::from(c.to_digit(16).unwrap_or(0));
+        }
+        let expoente = expoente.parse::<i32>().unwrap_or(0) - 4 * fracao.len() as i32;
+
+        valor * 2f64.powi(expoente)
+    }
+}
+
+// refaz as sequências de escape que o lexer resolveu ao ler o literal
+fn escapar(texto: &str, aspas: char) -> String {
+    let mut escapado = String::with_capacity(texto.len());
+
+    for c in texto.chars() {
+        match c {
+            '\n' => escapado.push_str("\\n"),
+            '\t' => escapado.push_str("\\t"),
+            '\r' => escapado.push_str("\\r"),
+            '\\' => escapado.push_str("\\\\"),
+            c if c == aspas => {
+                escapado.push('\\');
+                escapado.push(c);
+            }
+            // os demais de controle ('\0', '\a', ...) voltam em octal
+            c if c.is_control() => escapado.push_str(&format!("\\{:03o}", c as u32)),
+            c => escapado.push(c),
+        }
+    }
+
+    escapado
+}
+
+// posição de um trecho do código fonte: bytes [inicio, fim) e linha/coluna
+// do primeiro caractere (ambas começam em 1)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub inicio: usize,
+    pub fim: usize,
+    pub linha: usize,
+    pub coluna: usize,
+    // índice na tabela de arquivos do Preprocessador; 0 é o arquivo principal
+    pub arquivo: usize,
+}
+
+impl Span {
+    pub fn new(inicio: usize, fim: usize, linha: usize, coluna: usize) -> Self {
+        Self {
+            inicio,
+            fim,
+            linha,
+            coluna,
+            arquivo: 0,
+        }
+    }
+
+    pub fn no_arquivo(self, arquivo: usize) -> Span {
+        Span { arquivo, ..self }
+    }
+
+    // junta dois spans, do início deste até o fim do outro
+    pub fn ate(self, outro: Span) -> Span {
+        Span {
+            fim: outro.fim.max(self.fim),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
+    pub span: Span,
+}
+
+impl SpannedToken<'_> {
+    pub fn into_owned(self) -> SpannedToken<'static> {
+        SpannedToken {
+            token: self.token.into_owned(),
+            span: self.span,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum TipoTrivia {
+    // espaços e tabulações seguidos
+    Espaco,
+    // '\n' ou '\r\n' fora de diretivas
+    QuebraLinha,
+    ComentarioLinha,
+    ComentarioBloco,
+    // '\\' seguida de quebra de linha entre dois tokens
+    Continuacao,
+}
+
+// trecho da fonte sem significado para o parser, guardado no modo sem perdas
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Trivia<'a> {
+    pub tipo: TipoTrivia,
+    pub texto: &'a str,
+    pub span: Span,
+}
+
+// token com o texto exato da fonte e a trivia em volta: 'depois' vai até o fim da
+// linha do token (inclusive a quebra), 'antes' é o resto desde o token anterior
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct TokenComTrivia<'a> {
+    pub token: SpannedToken<'a>,
+    pub texto: &'a str,
+    pub antes: Vec<Trivia<'a>>,
+    pub depois: Vec<Trivia<'a>>,
+}
+
+// refaz a fonte byte a byte a partir do resultado de Lexer::tokenizar_com_trivia
+pub fn reconstruir_fonte(tokens: &[TokenComTrivia<'_>]) -> String {
+    let mut fonte = String::new();
+
+    for token in tokens {
+        for trivia in &token.antes {
+            fonte.push_str(trivia.texto);
+        }
+        fonte.push_str(token.texto);
+        for trivia in &token.depois {
+            fonte.push_str(trivia.texto);
+        }
+    }
+
+    fonte
 }
@@ -0,0 +1,253 @@
+// struct, union e enum: declaração antecipada, definição com declaradores, membros
+// anônimos, campos de bits, e o acesso a membros com '.' e '->'
+use CompiladorRustC::parser::{Campo, Enumerador};
+use CompiladorRustC::tipo::{Armazenamento, Definicao, TipoBase};
+use CompiladorRustC::{ExprKind, Lexer, Parser, Stmt, StmtKind, Tipo, Token};
+
+fn analisar(codigo: &str) -> Vec<Stmt<'_>> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+fn unica(codigo: &str) -> Stmt<'_> {
+    let mut programa = analisar(codigo);
+    assert_eq!(programa.len(), 1, "{:?}", programa);
+    programa.remove(0)
+}
+
+fn nome(campo: &Campo) -> Option<String> {
+    match &campo.nome {
+        Some(Token::Identificador(nome)) => Some(nome.to_string()),
+        _ => None,
+    }
+}
+
+fn nomes(campos: &[Campo]) -> Vec<Option<String>> {
+    campos.iter().map(nome).collect()
+}
+
+fn base<'t>(tipo: &'t Tipo) -> &'t TipoBase {
+    match tipo {
+        Tipo::Base { base, .. } => base,
+        _ => panic!("{:?}", tipo),
+    }
+}
+
+#[test]
+fn declaracao_antecipada() {
+    let StmtKind::Struct {
+        nome,
+        campos,
+        declaracao,
+    } = unica("struct No;").kind
+    else {
+        panic!();
+    };
+    assert_eq!(nome.as_deref(), Some("No"));
+    assert!(campos.is_none());
+    assert!(declaracao.is_none());
+
+    assert!(matches!(
+        unica("union U;").kind,
+        StmtKind::Union { campos: None, .. }
+    ));
+}
+
+#[test]
+fn definicao_com_declaradores() {
+    let StmtKind::Struct {
+        nome,
+        campos,
+        declaracao,
+    } = unica("struct Ponto { int x, y; struct Ponto *proximo; } origem, *atual;").kind
+    else {
+        panic!();
+    };
+    assert_eq!(nome.as_deref(), Some("Ponto"));
+
+    let campos = campos.unwrap();
+    assert_eq!(
+        nomes(&campos),
+        [Some("x".into()), Some("y".into()), Some("proximo".into())]
+    );
+    let Tipo::Ponteiro { apontado, .. } = &campos[2].tipo else {
+        panic!("{:?}", campos[2].tipo);
+    };
+    assert_eq!(base(apontado), &TipoBase::Struct(Some("Ponto".into())));
+
+    let declaracao = declaracao.unwrap();
+    let StmtKind::DeclaracaoVariavel { declaradores, .. } = &declaracao.kind else {
+        panic!("{:?}", declaracao);
+    };
+    assert_eq!(declaradores.len(), 2);
+    assert!(matches!(declaradores[1].tipo, Tipo::Ponteiro { .. }));
+}
+
+#[test]
+fn campos_de_bits() {
+    let StmtKind::Struct { campos, .. } =
+        unica("struct Flags { unsigned a : 1, b : 3; int : 0; int c; };").kind
+    else {
+        panic!();
+    };
+    let campos = campos.unwrap();
+    assert_eq!(
+        nomes(&campos),
+        [Some("a".into()), Some("b".into()), None, Some("c".into())]
+    );
+
+    let larguras: Vec<Option<i64>> = campos
+        .iter()
+        .map(|campo| match campo.bits.as_ref().map(|bits| &bits.kind) {
+            Some(ExprKind::NumeroInt(largura)) => Some(*largura),
+            Some(outro) => panic!("{:?}", outro),
+            None => None,
+        })
+        .collect();
+    assert_eq!(larguras, [Some(1), Some(3), Some(0), None]);
+}
+
+#[test]
+fn membros_anonimos_e_aninhados() {
+    let codigo = "\
+struct Valor {
+    union { int i; float f; };
+    struct Interna { int a; } interna;
+    int tag;
+};";
+    let StmtKind::Struct { campos, .. } = unica(codigo).kind else {
+        panic!();
+    };
+    let campos = campos.unwrap();
+    assert_eq!(
+        nomes(&campos),
+        [None, Some("interna".into()), Some("tag".into())]
+    );
+
+    // o membro anônimo leva a própria definição no tipo
+    let Tipo::Base {
+        base: TipoBase::Union(None),
+        definicao: Some(Definicao::Campos(membros)),
+        ..
+    } = &campos[0].tipo
+    else {
+        panic!("{:?}", campos[0].tipo);
+    };
+    assert_eq!(nomes(membros), [Some("i".into()), Some("f".into())]);
+
+    let Tipo::Base {
+        base: TipoBase::Struct(Some(interna)),
+        definicao: Some(Definicao::Campos(membros)),
+        ..
+    } = &campos[1].tipo
+    else {
+        panic!("{:?}", campos[1].tipo);
+    };
+    assert_eq!(interna, "Interna");
+    assert_eq!(nomes(membros), [Some("a".into())]);
+}
+
+#[test]
+fn definicao_depois_de_armazenamento_e_em_typedef() {
+    let StmtKind::Struct { declaracao, .. } = unica("static struct T { int z; } t;").kind else {
+        panic!();
+    };
+    let declaracao = declaracao.unwrap();
+    assert!(matches!(
+        declaracao.kind,
+        StmtKind::DeclaracaoVariavel {
+            armazenamento: Some(Armazenamento::Static),
+            ..
+        }
+    ));
+
+    // o typedef de uma struct anônima já vale como tipo na declaração seguinte
+    let programa = analisar("typedef struct { int x; } P; P p, *q;");
+    assert!(matches!(
+        programa[0].kind,
+        StmtKind::Struct { nome: None, .. }
+    ));
+    assert!(matches!(
+        programa[1].kind,
+        StmtKind::DeclaracaoVariavel { .. }
+    ));
+}
+
+#[test]
+fn enum_com_e_sem_valores() {
+    let StmtKind::Enum {
+        nome,
+        enumeradores,
+        declaracao,
+    } = unica("enum Cor { VERMELHO, VERDE = 5, AZUL, } cor;").kind
+    else {
+        panic!();
+    };
+    assert_eq!(nome.as_deref(), Some("Cor"));
+    assert!(declaracao.is_some());
+
+    let enumeradores = enumeradores.unwrap();
+    let resumo: Vec<(&str, bool)> = enumeradores
+        .iter()
+        .map(|Enumerador { nome, valor, .. }| (nome.as_str(), valor.is_some()))
+        .collect();
+    assert_eq!(
+        resumo,
+        [("VERMELHO", false), ("VERDE", true), ("AZUL", false)]
+    );
+
+    assert!(matches!(
+        unica("enum Cor;").kind,
+        StmtKind::Enum {
+            enumeradores: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn acesso_a_membros() {
+    let StmtKind::Expressao(expr) = unica("p->proximo->valor.x = a.b;").kind else {
+        panic!();
+    };
+    let ExprKind::Atribuicao { alvo, valor } = expr.kind else {
+        panic!();
+    };
+
+    // ((p->proximo)->valor).x
+    let ExprKind::AcessoMembro {
+        objeto,
+        operador,
+        membro,
+    } = alvo.kind
+    else {
+        panic!();
+    };
+    assert_eq!((operador, membro.as_str()), (Token::Ponto, "x"));
+
+    let ExprKind::AcessoMembro {
+        objeto,
+        operador,
+        membro,
+    } = objeto.kind
+    else {
+        panic!();
+    };
+    assert_eq!((operador, membro.as_str()), (Token::Seta, "valor"));
+
+    let ExprKind::AcessoMembro {
+        objeto,
+        operador,
+        membro,
+    } = objeto.kind
+    else {
+        panic!();
+    };
+    assert_eq!((operador, membro.as_str()), (Token::Seta, "proximo"));
+    assert!(matches!(objeto.kind, ExprKind::Variavel(Token::Identificador(ref p)) if p == "p"));
+
+    assert!(matches!(
+        valor.kind,
+        ExprKind::AcessoMembro { ref membro, .. } if membro == "b"
+    ));
+}
@@ -0,0 +1,128 @@
+// 'break' só dentro de laço ou switch e 'continue' só dentro de laço, conferidos por
+// semantico::verificar_controle_fluxo depois do Parser
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::semantico::verificar_controle_fluxo;
+use CompiladorRustC::{Diagnostic, Lexer, Parser, Stmt, StmtKind};
+
+fn verificar(codigo: &str) -> Vec<Diagnostic> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let programa = Parser::new(tokens).parse().unwrap();
+    verificar_controle_fluxo(&programa)
+}
+
+fn codigos_e_linhas(diagnosticos: &[Diagnostic]) -> Vec<(CodigoErro, usize)> {
+    diagnosticos
+        .iter()
+        .map(|diagnostico| (diagnostico.codigo, diagnostico.span.linha))
+        .collect()
+}
+
+#[test]
+fn break_e_continue_validos() {
+    let codigo = "\
+int f(int n) {
+    while (n) { if (n > 5) break; n--; continue; }
+    do { break; } while (1);
+    for (;;) { switch (n) { case 1: continue; default: break; } }
+    switch (n) { case 0: break; case 1: n++; }
+    inicio: for (;;) { { if (n) continue; } break; }
+    goto inicio;
+    return n;
+}
+";
+    let diagnosticos = verificar(codigo);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+}
+
+#[test]
+fn break_no_nivel_da_funcao() {
+    let diagnosticos = verificar("void f(void) {\n    int x = 1;\n    break;\n}\n");
+    assert_eq!(
+        codigos_e_linhas(&diagnosticos),
+        [(CodigoErro::BreakForaDeLaco, 3)]
+    );
+    assert_eq!(diagnosticos[0].span.coluna, 5);
+}
+
+#[test]
+fn continue_em_switch_fora_de_laco() {
+    let codigo = "\
+void f(int n) {
+    switch (n) {
+    case 1:
+        continue;
+    default:
+        break;
+    }
+}
+";
+    let diagnosticos = verificar(codigo);
+    assert_eq!(
+        codigos_e_linhas(&diagnosticos),
+        [(CodigoErro::ContinueForaDeLaco, 4)]
+    );
+    assert_eq!(diagnosticos[0].span.coluna, 9);
+}
+
+#[test]
+fn reporta_cada_ocorrencia() {
+    let codigo = "\
+void f(int n) {
+    if (n) break;
+    else { continue; }
+    while (n) { }
+    break;
+}
+void g(void) { continue; }
+";
+    assert_eq!(
+        codigos_e_linhas(&verificar(codigo)),
+        [
+            (CodigoErro::BreakForaDeLaco, 2),
+            (CodigoErro::ContinueForaDeLaco, 3),
+            (CodigoErro::BreakForaDeLaco, 5),
+            (CodigoErro::ContinueForaDeLaco, 7),
+        ]
+    );
+}
+
+#[test]
+fn rotulo_e_default_com_declaracao_vazia() {
+    let codigo = "\
+void f(int n) {
+    switch (n) { case 1: n++; default: ; }
+    for (;;) { if (n) goto fim; continue; fim: ; }
+    goto sair;
+sair: ;
+}
+";
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let programa = Parser::new(tokens).parse().unwrap();
+    assert!(verificar_controle_fluxo(&programa).is_empty());
+
+    let StmtKind::DeclaracaoFuncao { corpo, .. } = &programa[0].kind else {
+        panic!("{:?}", programa);
+    };
+    let StmtKind::Bloco { declaracoes } = &corpo.kind else {
+        panic!("{:?}", corpo);
+    };
+
+    let StmtKind::Switch { casos, .. } = &declaracoes[0].kind else {
+        panic!("{:?}", declaracoes[0]);
+    };
+    let default = casos.last().unwrap();
+    assert!(default.valor.is_none());
+    assert!(matches!(
+        default.declaracoes[..],
+        [Stmt {
+            kind: StmtKind::Vazia,
+            ..
+        }]
+    ));
+
+    let StmtKind::Rotulo { nome, declaracao } = &declaracoes[3].kind else {
+        panic!("{:?}", declaracoes[3]);
+    };
+    assert_eq!(nome, "sair");
+    assert!(matches!(declaracao.kind, StmtKind::Vazia));
+}
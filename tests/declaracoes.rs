@@ -0,0 +1,57 @@
+// 'Nome * ...' no começo de uma declaração: é declaração quando Nome é um tipo
+// (typedef ou externo, como FILE) e expressão quando é uma variável
+use CompiladorRustC::{Lexer, Parser, Stmt, StmtKind};
+
+fn analisar(codigo: &str) -> Vec<Stmt<'_>> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+// o tipo de cada declaração dentro do corpo da função 'f'
+fn corpo(codigo: &str) -> Vec<&'static str> {
+    let programa = analisar(codigo);
+    let StmtKind::DeclaracaoFuncao { corpo, .. } = &programa.last().unwrap().kind else {
+        panic!("{:?}", programa);
+    };
+    let StmtKind::Bloco { declaracoes } = &corpo.kind else {
+        panic!("{:?}", corpo);
+    };
+
+    declaracoes
+        .iter()
+        .map(|declaracao| match declaracao.kind {
+            StmtKind::DeclaracaoVariavel { .. } => "declaracao",
+            StmtKind::Expressao(_) => "expressao",
+            _ => "outra",
+        })
+        .collect()
+}
+
+#[test]
+fn variavel_vezes_algo_e_expressao() {
+    assert_eq!(
+        corpo("int f(int x) { int y; x * 2; x * y; y * x; x * (y + 1); }"),
+        [
+            "declaracao",
+            "expressao",
+            "expressao",
+            "expressao",
+            "expressao"
+        ]
+    );
+    // a variável global também conta
+    assert_eq!(corpo("int g; int f(void) { g * g; }"), ["expressao"]);
+}
+
+#[test]
+fn tipo_vezes_nome_e_declaracao() {
+    assert_eq!(
+        corpo("typedef int T; int f(void) { T *a; T **b; }"),
+        ["declaracao", "declaracao"]
+    );
+    // sem o cabeçalho, FILE é um tipo externo desconhecido
+    assert_eq!(
+        corpo("int f(void) { FILE *arquivo; string **v; FILE f2; }"),
+        ["declaracao", "declaracao", "declaracao"]
+    );
+}
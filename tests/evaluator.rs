@@ -0,0 +1,135 @@
+// avaliação de expressões constantes: cada operador, a promoção para float, o
+// unsigned das conversões usuais, o curto-circuito e os erros
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::{Lexer, Parser, Valor, avaliar};
+
+fn valor(codigo: &str) -> Valor {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let expressao = Parser::new(tokens).parse_virgula().unwrap();
+    avaliar(&expressao).unwrap_or_else(|erro| panic!("{}: {:?}", codigo, erro))
+}
+
+fn erro(codigo: &str) -> CodigoErro {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let expressao = Parser::new(tokens).parse_virgula().unwrap();
+    avaliar(&expressao).unwrap_err().codigo
+}
+
+fn inteiros(casos: &[(&str, i64)]) {
+    for (codigo, esperado) in casos {
+        assert_eq!(valor(codigo), Valor::Int(*esperado), "{}", codigo);
+    }
+}
+
+#[test]
+fn operadores_binarios_inteiros() {
+    inteiros(&[
+        ("7 + 3", 10),
+        ("7 - 10", -3),
+        ("6 * 7", 42),
+        // divisão e resto truncam em direção a zero
+        ("7 / 2", 3),
+        ("-7 / 2", -3),
+        ("7 % 3", 1),
+        ("-7 % 3", -1),
+        ("1 << 4", 16),
+        ("-16 >> 2", -4),
+        ("12 & 10", 8),
+        ("12 | 3", 15),
+        ("12 ^ 10", 6),
+        ("2 == 2", 1),
+        ("2 != 2", 0),
+        ("3 > 2", 1),
+        ("3 < 2", 0),
+        ("2 >= 2", 1),
+        ("3 <= 2", 0),
+        ("2 && 3", 1),
+        ("0 || 0", 0),
+        ("1 ? 2 : 3", 2),
+        ("0 ? 2 : 3", 3),
+    ]);
+}
+
+#[test]
+fn operadores_unarios_e_literais() {
+    inteiros(&[
+        ("-5", -5),
+        ("+5", 5),
+        ("!0", 1),
+        ("!7", 0),
+        ("~0", -1),
+        ("'A'", 65),
+        ("'\\n'", 10),
+        ("(1 + 2) * 3", 9),
+        ("(char)300", 44),
+        ("(unsigned char)-1", 255),
+        ("(short)65535", -1),
+        ("(int)2.9", 2),
+        ("(_Bool)5", 1),
+    ]);
+    assert_eq!(valor("-9223372036854775807 - 1"), Valor::Int(i64::MIN));
+    // estouro dá a volta
+    assert_eq!(valor("9223372036854775807 + 1"), Valor::Int(i64::MIN));
+}
+
+#[test]
+fn float_promove_o_outro_operando() {
+    assert_eq!(valor("1 + 0.5"), Valor::Float(1.5));
+    assert_eq!(valor("7 / 2.0"), Valor::Float(3.5));
+    assert_eq!(valor("-2.5"), Valor::Float(-2.5));
+    assert_eq!(valor("(double)3"), Valor::Float(3.0));
+    assert_eq!(valor("0.5 < 1"), Valor::Int(1));
+    assert_eq!(valor("!0.0"), Valor::Int(1));
+    assert_eq!(valor("0.5 && 2"), Valor::Int(1));
+}
+
+#[test]
+fn unsigned_vence_nas_conversoes_usuais() {
+    // -1 vira 0xFFFFFFFFFFFFFFFF ao encontrar um unsigned
+    assert_eq!(valor("-1 < 0u"), Valor::Int(0));
+    assert_eq!(valor("-1 > 0u"), Valor::Int(1));
+    assert_eq!(valor("0u - 1"), Valor::Unsigned(u64::MAX));
+    assert_eq!(valor("-1u"), Valor::Unsigned(u64::MAX));
+    assert_eq!(valor("~0u"), Valor::Unsigned(u64::MAX));
+    assert_eq!(valor("-8 / 2u"), Valor::Unsigned((u64::MAX - 7) / 2));
+    assert_eq!(valor("0xFFFFFFFFFFFFFFFF"), Valor::Unsigned(u64::MAX));
+    assert_eq!(valor("0xFFFFFFFFFFFFFFFF >> 60"), Valor::Unsigned(15));
+    assert_eq!(valor("(unsigned)-1"), Valor::Unsigned(0xFFFF_FFFF));
+
+    // o deslocamento fica com o tipo do operando esquerdo
+    assert_eq!(valor("-16 >> 2u"), Valor::Int(-4));
+    assert_eq!(valor("1u << 63"), Valor::Unsigned(1 << 63));
+}
+
+#[test]
+fn curto_circuito_nao_avalia_o_outro_lado() {
+    inteiros(&[
+        ("0 && 1 / 0", 0),
+        ("1 || 1 / 0", 1),
+        ("1 ? 2 : 1 / 0", 2),
+        ("0 ? 1 / 0 : 3", 3),
+    ]);
+    assert_eq!(erro("1 && 1 / 0"), CodigoErro::DivisaoPorZero);
+}
+
+#[test]
+fn erros() {
+    let casos = [
+        ("1 / 0", CodigoErro::DivisaoPorZero),
+        ("1 % 0", CodigoErro::DivisaoPorZero),
+        ("1u / 0", CodigoErro::DivisaoPorZero),
+        ("1.0 / 0", CodigoErro::DivisaoPorZero),
+        ("1 << 64", CodigoErro::DeslocamentoInvalido),
+        ("1 >> -1", CodigoErro::DeslocamentoInvalido),
+        ("1.5 % 2", CodigoErro::OperandoInvalido),
+        ("1.5 << 2", CodigoErro::OperandoInvalido),
+        ("~1.5", CodigoErro::OperandoInvalido),
+        ("x + 1", CodigoErro::ExpressaoNaoConstante),
+        ("f()", CodigoErro::ExpressaoNaoConstante),
+        ("\"texto\"", CodigoErro::ExpressaoNaoConstante),
+    ];
+
+    for (codigo, esperado) in casos {
+        assert_eq!(erro(codigo), esperado, "{}", codigo);
+    }
+}
@@ -0,0 +1,105 @@
+// vários declaradores numa declaração e listas de inicialização, aninhadas e com
+// designadores ('.x =' e '[2] =')
+use CompiladorRustC::parser::{Declarador, Designador, ItemInicializacao};
+use CompiladorRustC::{Expr, ExprKind, Lexer, Parser, StmtKind, Tipo, Token};
+
+fn declaradores(codigo: &str) -> Vec<Declarador<'_>> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let mut programa = Parser::new(tokens).parse().unwrap();
+    assert_eq!(programa.len(), 1, "{:?}", programa);
+    let StmtKind::DeclaracaoVariavel { declaradores, .. } = programa.remove(0).kind else {
+        panic!("esperada uma declaração de variável");
+    };
+    declaradores
+}
+
+// a lista escrita de novo, com os designadores, para comparar como texto
+fn imprimir(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::NumeroInt(valor) => valor.to_string(),
+        ExprKind::Variavel(Token::Identificador(nome)) => nome.to_string(),
+        ExprKind::ListaInicializacao(itens) => {
+            let itens: Vec<String> = itens.iter().map(imprimir_item).collect();
+            format!("{{{}}}", itens.join(", "))
+        }
+        outro => panic!("{:?}", outro),
+    }
+}
+
+fn imprimir_item(item: &ItemInicializacao) -> String {
+    let mut texto = String::new();
+    for designador in &item.designadores {
+        match designador {
+            Designador::Membro(membro) => texto.push_str(&format!(".{}", membro)),
+            Designador::Indice(indice) => texto.push_str(&format!("[{}]", imprimir(indice))),
+        }
+    }
+    if !item.designadores.is_empty() {
+        texto.push_str(" = ");
+    }
+    texto + &imprimir(&item.valor)
+}
+
+fn nome(declarador: &Declarador) -> String {
+    declarador.nome.to_string()
+}
+
+#[test]
+fn varios_declaradores() {
+    let declaradores = declaradores("int a = 1, b, c[3] = {1, 2, 3};");
+    let nomes: Vec<String> = declaradores.iter().map(nome).collect();
+    assert_eq!(nomes, ["a", "b", "c"]);
+
+    assert_eq!(
+        imprimir(declaradores[0].inicializador.as_ref().unwrap()),
+        "1"
+    );
+    assert!(declaradores[1].inicializador.is_none());
+    assert!(matches!(declaradores[2].tipo, Tipo::Array { .. }));
+    assert_eq!(
+        imprimir(declaradores[2].inicializador.as_ref().unwrap()),
+        "{1, 2, 3}"
+    );
+}
+
+#[test]
+fn cada_declarador_tem_o_proprio_tipo() {
+    let declaradores = declaradores("int x, *p, v[2], **pp;");
+    let tipos: Vec<&str> = declaradores
+        .iter()
+        .map(|declarador| match declarador.tipo {
+            Tipo::Base { .. } => "base",
+            Tipo::Ponteiro { ref apontado, .. } => match **apontado {
+                Tipo::Ponteiro { .. } => "ponteiro duplo",
+                _ => "ponteiro",
+            },
+            Tipo::Array { .. } => "array",
+            Tipo::Funcao { .. } => "funcao",
+        })
+        .collect();
+    assert_eq!(tipos, ["base", "ponteiro", "array", "ponteiro duplo"]);
+}
+
+#[test]
+fn listas_aninhadas_e_designadores() {
+    let casos = [
+        ("int m[2][2] = {{1, 2}, {3, 4}};", "{{1, 2}, {3, 4}}"),
+        ("int v[5] = {[2] = 5, 6, [0] = 1};", "{[2] = 5, 6, [0] = 1}"),
+        ("struct P p = {.x = 1, .y = 2};", "{.x = 1, .y = 2}"),
+        (
+            "struct R r = {.canto = {.x = 1}, .lados[1] = 3};",
+            "{.canto = {.x = 1}, .lados[1] = 3}",
+        ),
+        ("int a[2][2] = {[1][0] = 7};", "{[1][0] = 7}"),
+        // vírgula final e lista vazia
+        ("int v[] = {1, 2,};", "{1, 2}"),
+        ("struct P p = {};", "{}"),
+        ("int v[3] = {x, {y}};", "{x, {y}}"),
+    ];
+
+    for (codigo, esperado) in casos {
+        let declaradores = declaradores(codigo);
+        let inicializador = declaradores[0].inicializador.as_ref().unwrap();
+        assert_eq!(imprimir(inicializador), esperado, "{}", codigo);
+    }
+}
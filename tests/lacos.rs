@@ -0,0 +1,125 @@
+// while, do-while e for, com cada cláusula do for podendo faltar, e a declaração
+// vazia ';' usada como corpo
+use CompiladorRustC::{ExprKind, Lexer, Parser, Stmt, StmtKind};
+
+// as declarações do corpo da única função do código
+fn corpo(codigo: &str) -> Vec<Stmt<'_>> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let mut programa = Parser::new(tokens).parse().unwrap();
+    let StmtKind::DeclaracaoFuncao { corpo, .. } = programa.remove(0).kind else {
+        panic!("esperada uma função");
+    };
+    let StmtKind::Bloco { declaracoes } = corpo.kind else {
+        panic!("{:?}", corpo);
+    };
+    declaracoes
+}
+
+fn unica(codigo: &str) -> Stmt<'_> {
+    let mut declaracoes = corpo(codigo);
+    assert_eq!(declaracoes.len(), 1, "{:?}", declaracoes);
+    declaracoes.remove(0)
+}
+
+#[test]
+fn while_e_do_while() {
+    let StmtKind::While { condicao, corpo } = unica("void f(int n) { while (n > 0) n--; }").kind
+    else {
+        panic!();
+    };
+    assert!(matches!(condicao.kind, ExprKind::Binario { .. }));
+    assert!(matches!(corpo.kind, StmtKind::Expressao(_)));
+
+    let StmtKind::DoWhile { corpo, condicao } =
+        unica("void f(int n) { do { n--; } while (n); }").kind
+    else {
+        panic!();
+    };
+    assert!(matches!(corpo.kind, StmtKind::Bloco { .. }));
+    assert!(matches!(condicao.kind, ExprKind::Variavel(_)));
+}
+
+#[test]
+fn for_com_declaracao_na_inicializacao() {
+    let StmtKind::For {
+        inicializacao,
+        condicao,
+        incremento,
+        corpo,
+    } = unica("void f(int n) { for (int i = 0, j = n; i < j; i++, j--) { } }").kind
+    else {
+        panic!();
+    };
+
+    let Some(inicializacao) = inicializacao else {
+        panic!();
+    };
+    let StmtKind::DeclaracaoVariavel { declaradores, .. } = &inicializacao.kind else {
+        panic!("{:?}", inicializacao);
+    };
+    assert_eq!(declaradores.len(), 2);
+    assert!(condicao.is_some());
+    assert!(matches!(
+        incremento.map(|incremento| incremento.kind),
+        Some(ExprKind::Virgula { .. })
+    ));
+    assert!(matches!(corpo.kind, StmtKind::Bloco { .. }));
+}
+
+#[test]
+fn for_com_clausulas_vazias() {
+    // (inicialização, condição, incremento) presentes
+    let casos = [
+        ("for (;;) ;", (false, false, false)),
+        ("for (i = 0;;) ;", (true, false, false)),
+        ("for (; i < n;) ;", (false, true, false)),
+        ("for (;; i++) ;", (false, false, true)),
+        ("for (i = 0; i < n; i++) ;", (true, true, true)),
+    ];
+
+    for (laco, esperado) in casos {
+        let codigo = format!("void f(int i, int n) {{ {} }}", laco);
+        let StmtKind::For {
+            inicializacao,
+            condicao,
+            incremento,
+            corpo,
+        } = unica(&codigo).kind
+        else {
+            panic!("{}", laco);
+        };
+        assert_eq!(
+            (
+                inicializacao.is_some(),
+                condicao.is_some(),
+                incremento.is_some()
+            ),
+            esperado,
+            "{}",
+            laco
+        );
+        assert!(matches!(corpo.kind, StmtKind::Vazia), "{}", laco);
+    }
+}
+
+#[test]
+fn declaracao_vazia() {
+    let declaracoes = corpo("void f(int x) { while (1) ; if (x) ; else f(x); ; { ; } }");
+    assert!(matches!(
+        &declaracoes[0].kind,
+        StmtKind::While { corpo, .. } if matches!(corpo.kind, StmtKind::Vazia)
+    ));
+    assert!(matches!(
+        &declaracoes[1].kind,
+        StmtKind::If { bloco_then, bloco_else: Some(_), .. }
+            if matches!(bloco_then.kind, StmtKind::Vazia)
+    ));
+    assert!(matches!(declaracoes[2].kind, StmtKind::Vazia));
+
+    // o ';' depois do '}' de uma função
+    let tokens = Lexer::new("int f(void) { return 0; };")
+        .tokenizar()
+        .unwrap();
+    let programa = Parser::new(tokens).parse().unwrap();
+    assert!(matches!(programa[1].kind, StmtKind::Vazia));
+}
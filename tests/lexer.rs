@@ -0,0 +1,479 @@
+// classificação dos literais, escapes e prefixos, e os diagnósticos que o Lexer e o Parser
+// dão para eles; dígrafos, trígrafos e continuações de linha
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::parser::Operador;
+use CompiladorRustC::{
+    Base, Diagnostic, Dialeto, ExprKind, Lexer, LiteralNumero, PalavraChave, Parser, Prefixo,
+    Severidade, StmtKind, Token,
+};
+
+fn primeiro_token(codigo: &str) -> Token<'_> {
+    Lexer::new(codigo).tokenizar().unwrap().remove(0).token
+}
+
+// os tokens lidos, sem o Fundo
+fn tokens(lexer: Lexer) -> Vec<Token> {
+    lexer
+        .map(|token| token.token)
+        .filter(|token| *token != Token::Fundo)
+        .collect()
+}
+
+// diagnósticos do parser para a expressão dada
+fn diagnosticos_da_expressao(codigo: &str) -> Vec<Diagnostic> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let (_, diagnosticos) = Parser::new(tokens).parse_parcial();
+    diagnosticos
+}
+
+#[test]
+fn classifica_literais_numericos() {
+    let inteiros = [
+        ("0xFF", Base::Hexadecimal, "", 255),
+        ("0755", Base::Octal, "", 0o755),
+        ("0b1010", Base::Binaria, "", 10),
+        ("0", Base::Decimal, "", 0),
+        ("42uLL", Base::Decimal, "uLL", 42),
+        ("1Lu", Base::Decimal, "Lu", 1),
+    ];
+
+    for (codigo, base, sufixo, valor) in inteiros {
+        let Token::NumeroInt(numero) = primeiro_token(codigo) else {
+            panic!("'{}' deveria ser inteiro", codigo);
+        };
+        assert_eq!(numero.base, base, "{}", codigo);
+        assert_eq!(numero.sufixo, sufixo, "{}", codigo);
+        assert_eq!(numero.valor_inteiro(), Some(valor), "{}", codigo);
+    }
+
+    let flutuantes = [
+        (".5", Base::Decimal, "", 0.5),
+        ("1e-9", Base::Decimal, "", 1e-9),
+        ("0x1.8p3", Base::Hexadecimal, "", 12.0),
+        ("2.5f", Base::Decimal, "f", 2.5),
+        ("1.L", Base::Decimal, "L", 1.0),
+    ];
+
+    for (codigo, base, sufixo, valor) in flutuantes {
+        let Token::NumeroFloat(numero) = primeiro_token(codigo) else {
+            panic!("'{}' deveria ser de ponto flutuante", codigo);
+        };
+        assert_eq!(numero.base, base, "{}", codigo);
+        assert_eq!(numero.sufixo, sufixo, "{}", codigo);
+        assert_eq!(numero.valor_float(), valor, "{}", codigo);
+    }
+}
+
+#[test]
+fn numeros_invalidos_viram_token_de_erro() {
+    for codigo in ["08", "1lL", "1e5u", "0x", "0b102", "1e", "0x1.8", "1.2.3"] {
+        // o pp-number inteiro vira um só token, com o texto como foi escrito
+        let tokens = Lexer::new(codigo).tokenizar().unwrap();
+        let [erro, fim] = tokens.as_slice() else {
+            panic!("{}: {:?}", codigo, tokens);
+        };
+        let Token::ErroLexico(texto, diagnostico) = &erro.token else {
+            panic!("'{}' deveria dar erro: {:?}", codigo, erro.token);
+        };
+        assert_eq!(texto, codigo);
+        assert_eq!(diagnostico.codigo, CodigoErro::NumeroInvalido, "{}", codigo);
+        assert_eq!(fim.token, Token::Fundo);
+    }
+}
+
+#[test]
+fn numero_invalido_nao_impede_os_outros_erros() {
+    let tokens = Lexer::new("int a = 09;\nint b = ;\n").tokenizar().unwrap();
+    let (_, diagnosticos) = Parser::new(tokens).parse_parcial();
+    let codigos: Vec<_> = diagnosticos
+        .iter()
+        .map(|diagnostico| (diagnostico.codigo, diagnostico.span.linha))
+        .collect();
+    assert_eq!(
+        codigos,
+        [
+            (CodigoErro::NumeroInvalido, 1),
+            (CodigoErro::PrimarioEsperado, 2)
+        ]
+    );
+}
+
+#[test]
+fn constantes_fora_do_intervalo() {
+    // não cabe em 64 bits
+    let diagnosticos = diagnosticos_da_expressao("99999999999999999999;");
+    assert_eq!(diagnosticos.len(), 1, "{:?}", diagnosticos);
+    assert_eq!(diagnosticos[0].codigo, CodigoErro::ConstanteForaDoIntervalo);
+    assert_eq!(diagnosticos[0].severidade, Severidade::Erro);
+
+    // cabe em unsigned long long, mas um decimal sem 'u' deveria caber em long long
+    let diagnosticos = diagnosticos_da_expressao("18446744073709551615;");
+    assert_eq!(diagnosticos.len(), 1, "{:?}", diagnosticos);
+    assert_eq!(diagnosticos[0].codigo, CodigoErro::ConstanteForaDoIntervalo);
+    assert_eq!(diagnosticos[0].severidade, Severidade::Aviso);
+
+    for codigo in ["18446744073709551615u;", "0xFFFFFFFFFFFFFFFF;", "1.5;"] {
+        let diagnosticos = diagnosticos_da_expressao(codigo);
+        assert!(diagnosticos.is_empty(), "{}: {:?}", codigo, diagnosticos);
+    }
+
+    let diagnosticos = diagnosticos_da_expressao("1e999;");
+    assert_eq!(diagnosticos.len(), 1, "{:?}", diagnosticos);
+    assert_eq!(diagnosticos[0].severidade, Severidade::Aviso);
+}
+
+#[test]
+fn avisos_nao_fazem_parse_falhar() {
+    let codigo = "long x = 18446744073709551615;\ndouble d = 1e999;\n";
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let programa = Parser::new(tokens).parse().unwrap();
+    assert_eq!(programa.len(), 2);
+
+    let tokens = Lexer::new("long x = 99999999999999999999;")
+        .tokenizar()
+        .unwrap();
+    let diagnosticos = Parser::new(tokens).parse().unwrap_err();
+    assert_eq!(diagnosticos[0].severidade, Severidade::Erro);
+}
+
+#[test]
+fn resolve_sequencias_de_escape() {
+    let casos = [
+        (r#""a\tb\n""#, "a\tb\n"),
+        (r#""\\ \' \" \?""#, "\\ ' \" ?"),
+        (r#""\a\b\f\v\r""#, "\x07\x08\x0C\x0B\r"),
+        (r#""\0\101\1234""#, "\0A\x534"),
+        (r#""\x41\x7e""#, "A~"),
+        (r#""\u00e9\U0001F600""#, "é😀"),
+        // sem escapes o texto fica emprestado da fonte
+        (r#""sem escapes""#, "sem escapes"),
+    ];
+
+    for (codigo, esperado) in casos {
+        let Token::Texto(texto, Prefixo::Nenhum) = primeiro_token(codigo) else {
+            panic!("'{}' deveria ser string", codigo);
+        };
+        assert_eq!(texto, esperado, "{}", codigo);
+    }
+
+    assert_eq!(
+        primeiro_token(r"'\n'"),
+        Token::ConteudoChar('\n', Prefixo::Nenhum)
+    );
+    assert_eq!(
+        primeiro_token(r"'\xff'"),
+        Token::ConteudoChar('\u{ff}', Prefixo::Nenhum)
+    );
+}
+
+#[test]
+fn prefixos_de_literal() {
+    let casos = [
+        ("L\"a\"", Prefixo::Largo),
+        ("u8\"a\"", Prefixo::Utf8),
+        ("u\"a\"", Prefixo::Utf16),
+        ("U\"a\"", Prefixo::Utf32),
+    ];
+    for (codigo, prefixo) in casos {
+        assert_eq!(
+            primeiro_token(codigo),
+            Token::Texto("a".into(), prefixo),
+            "{}",
+            codigo
+        );
+    }
+
+    assert_eq!(
+        primeiro_token("L'x'"),
+        Token::ConteudoChar('x', Prefixo::Largo)
+    );
+    // sem a aspa colada, 'L' e 'u8' são identificadores
+    assert_eq!(primeiro_token("L"), Token::Identificador("L".into()));
+    assert_eq!(
+        primeiro_token("u8 \"a\""),
+        Token::Identificador("u8".into())
+    );
+
+    // os escapes octais e '\x' são limitados pela unidade de código do prefixo
+    assert_eq!(
+        primeiro_token(r"L'\777'"),
+        Token::ConteudoChar('\u{1ff}', Prefixo::Largo)
+    );
+    assert_eq!(
+        primeiro_token(r#"u"\xFFFF""#),
+        Token::Texto("\u{ffff}".into(), Prefixo::Utf16)
+    );
+}
+
+#[test]
+fn concatena_literais_adjacentes() {
+    let tokens = Lexer::new(r#""a" L"b" "c";"#).tokenizar().unwrap();
+    let (stmts, diagnosticos) = Parser::new(tokens).parse_parcial();
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    let StmtKind::Expressao(expr) = &stmts[0].kind else {
+        panic!("{:?}", stmts);
+    };
+    let ExprKind::StringLiteral(texto, prefixo) = &expr.kind else {
+        panic!("{:?}", expr);
+    };
+    assert_eq!((texto.as_ref(), *prefixo), ("abc", Prefixo::Largo));
+
+    let diagnosticos = diagnosticos_da_expressao(r#"u8"a" L"b";"#);
+    assert_eq!(diagnosticos.len(), 1, "{:?}", diagnosticos);
+    assert_eq!(
+        diagnosticos[0].mensagem,
+        "Literais com prefixos 'u8' e 'L' não podem ser concatenados"
+    );
+}
+
+#[test]
+fn literais_com_erro() {
+    let casos = [
+        ("'a", CodigoErro::CharNaoFechado),
+        ("\"abc\nint x;", CodigoErro::StringNaoFechada),
+        ("''", CodigoErro::CharNaoFechado),
+        ("'ab'", CodigoErro::CharNaoFechado),
+        (r#""\q""#, CodigoErro::EscapeInvalido),
+        (r#""\x""#, CodigoErro::EscapeInvalido),
+        (r#""\u12""#, CodigoErro::EscapeInvalido),
+        (r"'\777'", CodigoErro::EscapeInvalido),
+        (r#""\x100""#, CodigoErro::EscapeInvalido),
+        (r#"u8"\400""#, CodigoErro::EscapeInvalido),
+        (r#"u"\x10000""#, CodigoErro::EscapeInvalido),
+    ];
+
+    for (codigo, esperado) in casos {
+        // o lexer não para: o erro vai no token e só o parser o reporta
+        let tokens = Lexer::new(codigo).tokenizar().unwrap();
+        let Token::ErroLexico(texto, diagnostico) = &tokens[0].token else {
+            panic!("'{}' deveria dar erro: {:?}", codigo, tokens[0].token);
+        };
+        assert_eq!(diagnostico.codigo, esperado, "{}", codigo);
+        assert!(codigo.starts_with(texto.as_ref()), "{}: {}", codigo, texto);
+
+        let diagnosticos = diagnosticos_da_expressao(codigo);
+        assert_eq!(diagnosticos[0].codigo, esperado, "{}", codigo);
+    }
+
+    // depois do literal com erro, a leitura continua normalmente
+    let tokens = Lexer::new("\"\\q\" x").tokenizar().unwrap();
+    assert_eq!(tokens[1].token, Token::Identificador("x".into()));
+}
+
+#[test]
+fn digrafos() {
+    assert_eq!(
+        tokens(Lexer::new("<: :> <% %> %: %:%:")),
+        [
+            Token::AbreColchete,
+            Token::FechaColchete,
+            Token::AbreChave,
+            Token::FechaChave,
+            Token::Cerquilha,
+            Token::CerquilhaDupla,
+        ]
+    );
+    // '%:' no começo da linha abre diretiva, como '#'
+    assert_eq!(
+        tokens(Lexer::new("%:define N 1")),
+        [
+            Token::Diretiva("define".into()),
+            Token::Identificador("N".into()),
+            Token::NumeroInt(LiteralNumero::decimal("1")),
+        ]
+    );
+}
+
+#[test]
+fn trigrafos_so_quando_ativados() {
+    let codigo = "??( ??) ??< ??> ??! ??' ??-";
+
+    assert_eq!(
+        tokens(Lexer::new(codigo).com_trigrafos(true)),
+        [
+            Token::AbreColchete,
+            Token::FechaColchete,
+            Token::AbreChave,
+            Token::FechaChave,
+            Token::BarraVertical,
+            Token::Circunflexo,
+            Token::Til,
+        ]
+    );
+
+    // desativados, '??(' é '?', '?' e '('
+    assert_eq!(
+        tokens(Lexer::new("??(")),
+        [
+            Token::Interrogacao,
+            Token::Interrogacao,
+            Token::AbreParentesis
+        ]
+    );
+    assert_eq!(
+        primeiro_token(r#""??=""#),
+        Token::Texto("??=".into(), Prefixo::Nenhum)
+    );
+
+    let Token::Texto(texto, _) = Lexer::new(r#""??=""#)
+        .com_trigrafos(true)
+        .tokenizar()
+        .unwrap()
+        .remove(0)
+        .token
+    else {
+        panic!();
+    };
+    assert_eq!(texto, "#");
+}
+
+#[test]
+fn trigrafo_de_barra_continua_a_linha() {
+    let lexer = Lexer::new("in??/\nt x;").com_trigrafos(true);
+    assert_eq!(
+        tokens(lexer),
+        [
+            Token::PalavraChave(PalavraChave::Int),
+            Token::Identificador("x".into()),
+            Token::PontoVirgula,
+        ]
+    );
+
+    // desativados, '??/' é só texto e a quebra de linha separa os tokens
+    assert_eq!(
+        tokens(Lexer::new("in??/\nt")),
+        [
+            Token::Identificador("in".into()),
+            Token::Interrogacao,
+            Token::Interrogacao,
+            Token::Divisao,
+            Token::Identificador("t".into()),
+        ]
+    );
+}
+
+#[test]
+fn span_cobre_os_bytes_originais_depois_de_continuacao() {
+    let codigo = "int\nin\\\nt x;\nyy\\\r\nz w;";
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let sem_quebras: Vec<_> = tokens
+        .iter()
+        .filter(|token| token.token != Token::QuebraLinha)
+        .collect();
+
+    // 'in\\\nt' é um 'int' que começa na linha 2 e termina na 3
+    let int = sem_quebras[1];
+    assert_eq!(int.token, Token::PalavraChave(PalavraChave::Int));
+    assert_eq!(&codigo[int.span.inicio..int.span.fim], "in\\\nt");
+    assert_eq!((int.span.linha, int.span.coluna), (2, 1));
+
+    let x = sem_quebras[2];
+    assert_eq!(&codigo[x.span.inicio..x.span.fim], "x");
+    assert_eq!((x.span.linha, x.span.coluna), (3, 3));
+
+    let yyz = sem_quebras[4];
+    assert_eq!(yyz.token, Token::Identificador("yyz".into()));
+    assert_eq!(&codigo[yyz.span.inicio..yyz.span.fim], "yy\\\r\nz");
+
+    let w = sem_quebras[5];
+    assert_eq!((w.span.linha, w.span.coluna), (5, 3));
+}
+
+#[test]
+fn menor_seguido_de_escopo_em_cpp() {
+    // em C, '<:' é sempre o dígrafo de '['
+    assert_eq!(
+        tokens(Lexer::new("a<::b>")),
+        [
+            Token::Identificador("a".into()),
+            Token::AbreColchete,
+            Token::DoisPontos,
+            Token::Identificador("b".into()),
+            Token::Maior,
+        ]
+    );
+
+    assert_eq!(
+        tokens(Lexer::new("a<::b>").com_dialeto(Dialeto::Cpp)),
+        [
+            Token::Identificador("a".into()),
+            Token::Menor,
+            Token::DoisPontos,
+            Token::DoisPontos,
+            Token::Identificador("b".into()),
+            Token::Maior,
+        ]
+    );
+
+    // '<:::' e '<::>' continuam sendo dígrafos em C++
+    let cpp = |codigo| tokens(Lexer::new(codigo).com_dialeto(Dialeto::Cpp));
+    assert_eq!(
+        cpp("<:::"),
+        [Token::AbreColchete, Token::DoisPontos, Token::DoisPontos]
+    );
+    assert_eq!(cpp("<::>"), [Token::AbreColchete, Token::FechaColchete]);
+}
+
+#[test]
+fn palavras_chave_dependem_do_dialeto() {
+    let c = |codigo| tokens(Lexer::new(codigo));
+    let cpp = |codigo| tokens(Lexer::new(codigo).com_dialeto(Dialeto::Cpp));
+
+    // só de C++: nomes comuns em C
+    for nome in ["class", "bool", "nullptr", "using", "and"] {
+        assert_eq!(c(nome), [Token::Identificador(nome.into())], "{}", nome);
+    }
+    assert_eq!(
+        cpp("class bool nullptr using"),
+        [
+            Token::PalavraChave(PalavraChave::Class),
+            Token::PalavraChave(PalavraChave::Bool),
+            Token::PalavraChave(PalavraChave::Nullptr),
+            Token::PalavraChave(PalavraChave::Using),
+        ]
+    );
+
+    // só de C: nomes comuns em C++
+    assert_eq!(c("restrict"), [Token::PalavraChave(PalavraChave::Restrict)]);
+    assert_eq!(cpp("restrict"), [Token::Identificador("restrict".into())]);
+    assert_eq!(cpp("_Bool"), [Token::Identificador("_Bool".into())]);
+
+    // nos dois
+    assert_eq!(c("int"), cpp("int"));
+}
+
+#[test]
+fn grafias_alternativas_em_cpp() {
+    let cpp = |codigo| tokens(Lexer::new(codigo).com_dialeto(Dialeto::Cpp));
+    assert_eq!(
+        cpp("and and_eq bitand bitor compl not not_eq or or_eq xor xor_eq"),
+        [
+            Token::EComercialDuplo,
+            Token::EComercialIgual,
+            Token::EComercial,
+            Token::BarraVertical,
+            Token::Til,
+            Token::Negacao,
+            Token::Diferente,
+            Token::BarraVerticalDupla,
+            Token::BarraVerticalIgual,
+            Token::Circunflexo,
+            Token::CircunflexoIgual,
+        ]
+    );
+    // só o nome inteiro
+    assert_eq!(cpp("android"), [Token::Identificador("android".into())]);
+
+    let tokens = Lexer::new("not a or b and compl c")
+        .com_dialeto(Dialeto::Cpp)
+        .tokenizar()
+        .unwrap();
+    let expressao = Parser::new(tokens).parse_virgula().unwrap();
+    assert!(matches!(
+        expressao.kind,
+        ExprKind::Binario {
+            operador: Operador::BarraVerticalDupla,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,294 @@
+// fixa a precedência e a associatividade de cada operador de C, do mais forte
+// (pós-fixos) ao mais fraco (vírgula); cada expressão é impressa com todos os
+// parênteses implícitos para comparar a árvore inteira de uma vez
+use CompiladorRustC::parser::{ExprKind, Operador, OperandoSizeof};
+use CompiladorRustC::{Expr, Lexer, Parser, Tipo, Token};
+
+fn parentizar(codigo: &str) -> String {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let expr = Parser::new(tokens).parse_virgula().unwrap();
+    imprimir(&expr)
+}
+
+fn imprimir(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::NumeroInt(valor) => valor.to_string(),
+        ExprKind::Variavel(Token::Identificador(nome)) => nome.to_string(),
+        ExprKind::Agrupamento(interna) => imprimir(interna),
+        ExprKind::Binario {
+            esquerda,
+            operador,
+            direita,
+        } => format!(
+            "({} {} {})",
+            imprimir(esquerda),
+            simbolo_operador(operador),
+            imprimir(direita)
+        ),
+        ExprKind::Unario { operador, direita } => {
+            format!("({}{})", simbolo(operador), imprimir(direita))
+        }
+        ExprKind::Posfixa {
+            expressao,
+            operador,
+        } => format!("({}{})", imprimir(expressao), simbolo(operador)),
+        ExprKind::AcessoArray { nome, indice } => {
+            format!("({}[{}])", imprimir(nome), imprimir(indice))
+        }
+        ExprKind::AcessoMembro {
+            objeto,
+            operador,
+            membro,
+        } => format!("({}{}{})", imprimir(objeto), simbolo(operador), membro),
+        ExprKind::ChamadaFuncao { callee, argumentos } => {
+            let argumentos: Vec<String> = argumentos.iter().map(imprimir).collect();
+            format!("{}({})", imprimir(callee), argumentos.join(", "))
+        }
+        ExprKind::Atribuicao { alvo, valor } => {
+            format!("({} = {})", imprimir(alvo), imprimir(valor))
+        }
+        ExprKind::AtribuicaoComposta {
+            alvo,
+            operador,
+            valor,
+        } => format!(
+            "({} {} {})",
+            imprimir(alvo),
+            simbolo(operador),
+            imprimir(valor)
+        ),
+        ExprKind::Ternario {
+            condicao,
+            entao,
+            senao,
+        } => format!(
+            "({} ? {} : {})",
+            imprimir(condicao),
+            imprimir(entao),
+            imprimir(senao)
+        ),
+        ExprKind::Virgula { esquerda, direita } => {
+            format!("({}, {})", imprimir(esquerda), imprimir(direita))
+        }
+        ExprKind::Sizeof(OperandoSizeof::Tipo(tipo)) => format!("sizeof({})", nome_tipo(tipo)),
+        ExprKind::Sizeof(OperandoSizeof::Expressao(operando)) => {
+            format!("(sizeof {})", imprimir(operando))
+        }
+        ExprKind::Cast { tipo, expressao } => {
+            format!("(({}) {})", nome_tipo(tipo), imprimir(expressao))
+        }
+        outro => panic!("expressão sem forma impressa: {:?}", outro),
+    }
+}
+
+fn nome_tipo(tipo: &Tipo) -> String {
+    match tipo {
+        Tipo::Base { base, .. } => format!("{:?}", base).to_lowercase(),
+        Tipo::Ponteiro { apontado, .. } => format!("{} *", nome_tipo(apontado)),
+        outro => panic!("tipo sem forma impressa: {:?}", outro),
+    }
+}
+
+fn simbolo_operador(operador: &Operador) -> &'static str {
+    match operador {
+        Operador::Mais => "+",
+        Operador::Menos => "-",
+        Operador::Asterisco => "*",
+        Operador::Divisao => "/",
+        Operador::Modulo => "%",
+        Operador::DeslocamentoEsq => "<<",
+        Operador::DeslocamentoDir => ">>",
+        Operador::Menor => "<",
+        Operador::Maior => ">",
+        Operador::MenorOuIgual => "<=",
+        Operador::MaiorOuIgual => ">=",
+        Operador::Comparar => "==",
+        Operador::Diferente => "!=",
+        Operador::EComercial => "&",
+        Operador::Xor => "^",
+        Operador::BarraVertical => "|",
+        Operador::EComercialDuplo => "&&",
+        Operador::BarraVerticalDupla => "||",
+    }
+}
+
+fn simbolo(token: &Token) -> &'static str {
+    match token {
+        Token::Mais => "+",
+        Token::Menos => "-",
+        Token::Asterisco => "*",
+        Token::EComercial => "&",
+        Token::Negacao => "!",
+        Token::Til => "~",
+        Token::Incremento => "++",
+        Token::Decremento => "--",
+        Token::Ponto => ".",
+        Token::Seta => "->",
+        Token::SomaIgual => "+=",
+        Token::SubtracaoIgual => "-=",
+        Token::MultiplicacaoIgual => "*=",
+        Token::DivisaoIgual => "/=",
+        Token::ModuloIgual => "%=",
+        Token::DeslocamentoEsqIgual => "<<=",
+        Token::DeslocamentoDirIgual => ">>=",
+        Token::EComercialIgual => "&=",
+        Token::CircunflexoIgual => "^=",
+        Token::BarraVerticalIgual => "|=",
+        outro => panic!("token sem símbolo: {:?}", outro),
+    }
+}
+
+#[test]
+fn posfixos_ligam_mais_forte_que_prefixos() {
+    assert_eq!(parentizar("*p++"), "(*(p++))");
+    assert_eq!(parentizar("-a[0]"), "(-(a[0]))");
+    assert_eq!(parentizar("&s.x"), "(&(s.x))");
+    assert_eq!(parentizar("!p->ok"), "(!(p->ok))");
+    assert_eq!(parentizar("++*p"), "(++(*p))");
+    assert_eq!(parentizar("*p--"), "(*(p--))");
+}
+
+#[test]
+fn posfixos_associam_a_esquerda() {
+    assert_eq!(parentizar("a[i][j]"), "((a[i])[j])");
+    assert_eq!(parentizar("a.b->c"), "((a.b)->c)");
+    assert_eq!(parentizar("a[i]++"), "((a[i])++)");
+    assert_eq!(parentizar("f(x)[0]"), "(f(x)[0])");
+}
+
+#[test]
+fn prefixos_associam_a_direita() {
+    assert_eq!(parentizar("- -a"), "(-(-a))");
+    assert_eq!(parentizar("~-a"), "(~(-a))");
+    assert_eq!(parentizar("!*&a"), "(!(*(&a)))");
+    assert_eq!(parentizar("+a"), "(+a)");
+}
+
+#[test]
+fn sizeof_e_unario() {
+    assert_eq!(parentizar("sizeof x + 1"), "((sizeof x) + 1)");
+    assert_eq!(parentizar("sizeof a[0]"), "(sizeof (a[0]))");
+    assert_eq!(parentizar("sizeof *p"), "(sizeof (*p))");
+    assert_eq!(parentizar("sizeof(int) * 2"), "(sizeof(int) * 2)");
+    assert_eq!(parentizar("sizeof(char *)"), "sizeof(char *)");
+    assert_eq!(parentizar("sizeof(x)"), "(sizeof x)");
+}
+
+#[test]
+fn conversao_e_unaria() {
+    assert_eq!(parentizar("(int)a + b"), "(((int) a) + b)");
+    assert_eq!(parentizar("(int)-a"), "((int) (-a))");
+    assert_eq!(parentizar("(char *)p[1]"), "((char *) (p[1]))");
+    assert_eq!(parentizar("*(int *)p"), "(*((int *) p))");
+    assert_eq!(parentizar("(long)(double)x"), "((long) ((double) x))");
+}
+
+#[test]
+fn multiplicativos_acima_de_unarios_e_aditivos() {
+    assert_eq!(parentizar("-a * b"), "((-a) * b)");
+    assert_eq!(parentizar("a + b * c"), "(a + (b * c))");
+    assert_eq!(parentizar("a - b / c"), "(a - (b / c))");
+    assert_eq!(parentizar("a * b % c"), "((a * b) % c)");
+    assert_eq!(parentizar("a / b * c"), "((a / b) * c)");
+}
+
+#[test]
+fn aditivos_associam_a_esquerda() {
+    assert_eq!(parentizar("a - b - c"), "((a - b) - c)");
+    assert_eq!(parentizar("a - b + c"), "((a - b) + c)");
+}
+
+#[test]
+fn deslocamentos_abaixo_de_aditivos() {
+    assert_eq!(parentizar("a << b + c"), "(a << (b + c))");
+    assert_eq!(parentizar("a >> b - c"), "(a >> (b - c))");
+    assert_eq!(parentizar("a << b >> c"), "((a << b) >> c)");
+}
+
+#[test]
+fn relacionais_abaixo_de_deslocamentos() {
+    assert_eq!(parentizar("a < b << c"), "(a < (b << c))");
+    assert_eq!(parentizar("a >= b >> c"), "(a >= (b >> c))");
+    assert_eq!(parentizar("a < b > c"), "((a < b) > c)");
+    assert_eq!(parentizar("a <= b >= c"), "((a <= b) >= c)");
+}
+
+#[test]
+fn igualdade_abaixo_de_relacionais() {
+    assert_eq!(parentizar("a == b < c"), "(a == (b < c))");
+    assert_eq!(parentizar("a != b >= c"), "(a != (b >= c))");
+    assert_eq!(parentizar("a < b == c > d"), "((a < b) == (c > d))");
+    assert_eq!(parentizar("a == b != c"), "((a == b) != c)");
+}
+
+#[test]
+fn bit_a_bit_abaixo_de_igualdade_na_ordem_and_xor_or() {
+    assert_eq!(parentizar("a & b == c"), "(a & (b == c))");
+    assert_eq!(parentizar("a ^ b & c"), "(a ^ (b & c))");
+    assert_eq!(parentizar("a & b ^ c"), "((a & b) ^ c)");
+    assert_eq!(parentizar("a | b ^ c"), "(a | (b ^ c))");
+    assert_eq!(parentizar("a ^ b | c"), "((a ^ b) | c)");
+    assert_eq!(parentizar("a | b & c ^ d"), "(a | ((b & c) ^ d))");
+    assert_eq!(parentizar("a ^ b ^ c"), "((a ^ b) ^ c)");
+}
+
+#[test]
+fn logicos_abaixo_de_bit_a_bit() {
+    assert_eq!(parentizar("a && b | c"), "(a && (b | c))");
+    assert_eq!(parentizar("a || b && c"), "(a || (b && c))");
+    assert_eq!(parentizar("a && b || c && d"), "((a && b) || (c && d))");
+    assert_eq!(parentizar("a || b || c"), "((a || b) || c)");
+}
+
+#[test]
+fn ternario_abaixo_de_logicos_e_associa_a_direita() {
+    assert_eq!(parentizar("a || b ? c : d"), "((a || b) ? c : d)");
+    assert_eq!(parentizar("a ? b : c ? d : e"), "(a ? b : (c ? d : e))");
+    assert_eq!(parentizar("a ? b ? c : d : e"), "(a ? (b ? c : d) : e)");
+    assert_eq!(parentizar("a ? b, c : d"), "(a ? (b, c) : d)");
+}
+
+#[test]
+fn atribuicao_abaixo_do_ternario_e_associa_a_direita() {
+    assert_eq!(parentizar("x = a ? b : c"), "(x = (a ? b : c))");
+    assert_eq!(parentizar("a = b = c"), "(a = (b = c))");
+    assert_eq!(parentizar("a += b *= c"), "(a += (b *= c))");
+    assert_eq!(parentizar("*p = a + b"), "((*p) = (a + b))");
+    assert_eq!(parentizar("a[i] = b || c"), "((a[i]) = (b || c))");
+}
+
+#[test]
+fn todas_as_atribuicoes_compostas() {
+    for operador in ["+=", "-=", "*=", "/=", "%=", "<<=", ">>=", "&=", "^=", "|="] {
+        assert_eq!(
+            parentizar(&format!("a {} b | c", operador)),
+            format!("(a {} (b | c))", operador)
+        );
+    }
+}
+
+#[test]
+fn virgula_e_a_mais_fraca_e_associa_a_esquerda() {
+    assert_eq!(parentizar("a = 1, b = 2"), "((a = 1), (b = 2))");
+    assert_eq!(parentizar("a, b, c"), "((a, b), c)");
+    assert_eq!(parentizar("f(a, b), c"), "(f(a, b), c)");
+}
+
+#[test]
+fn parenteses_mudam_a_precedencia() {
+    assert_eq!(parentizar("(a + b) * c"), "((a + b) * c)");
+    assert_eq!(parentizar("(*p)++"), "((*p)++)");
+    assert_eq!(parentizar("(-a)[0]"), "((-a)[0])");
+    assert_eq!(parentizar("a - (b - c)"), "(a - (b - c))");
+}
+
+#[test]
+fn chamadas_sao_posfixas() {
+    assert_eq!(parentizar("(*fp)(x)"), "(*fp)(x)");
+    assert_eq!(parentizar("tbl[i](x, y)"), "(tbl[i])(x, y)");
+    assert_eq!(parentizar("obj.metodo()"), "(obj.metodo)()");
+    assert_eq!(parentizar("f(a)(b)"), "f(a)(b)");
+    assert_eq!(parentizar("*f(x)"), "(*f(x))");
+    assert_eq!(parentizar("-g()[0]"), "(-(g()[0]))");
+    assert_eq!(parentizar("p->f(x)->g"), "((p->f)(x)->g)");
+}
@@ -0,0 +1,449 @@
+// expansão de macros, condicionais, inclusões e diretivas de linha do Preprocessador;
+// a saída é comparada como texto, com os tokens separados por espaço
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::{
+    Diagnostic, Dialeto, Lexer, Preprocessador, Severidade, SistemaArquivosMemoria, SpannedToken,
+    Token,
+};
+
+fn processar(preprocessador: &mut Preprocessador, codigo: &str) -> (String, Vec<Diagnostic>) {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let (saida, diagnosticos) = preprocessador.processar_parcial(tokens);
+    (texto(&saida), diagnosticos)
+}
+
+fn texto(tokens: &[SpannedToken]) -> String {
+    tokens
+        .iter()
+        .filter(|token| !matches!(token.token, Token::Fundo | Token::QuebraLinha))
+        .map(|token| token.token.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// saída de um código que não pode gerar diagnósticos
+fn expandir(codigo: &str) -> String {
+    let (saida, diagnosticos) = processar(&mut Preprocessador::new(), codigo);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    saida
+}
+
+fn codigos(diagnosticos: &[Diagnostic]) -> Vec<CodigoErro> {
+    diagnosticos
+        .iter()
+        .map(|diagnostico| diagnostico.codigo)
+        .collect()
+}
+
+// main.c, numa pasta da memória, com os cabeçalhos dados
+fn com_arquivos(arquivos: &[(&str, &str)]) -> Preprocessador {
+    let mut sistema_arquivos = SistemaArquivosMemoria::new();
+    for (caminho, conteudo) in arquivos {
+        sistema_arquivos.inserir(format!("proj/{}", caminho), *conteudo);
+    }
+
+    let mut preprocessador = Preprocessador::new();
+    preprocessador.usar_sistema_arquivos(Box::new(sistema_arquivos));
+    preprocessador.definir_arquivo_principal("proj/main.c");
+    preprocessador
+}
+
+#[test]
+fn macros_de_objeto_e_de_funcao() {
+    assert_eq!(expandir("#define N 10\nint a = N;"), "int a = 10 ;");
+    assert_eq!(
+        expandir("#define SQ(x) ((x) * (x))\nSQ(N + 1)"),
+        "( ( N + 1 ) * ( N + 1 ) )"
+    );
+    assert_eq!(expandir("#define EMPTY\nEMPTY 1 EMPTY"), "1");
+    // o espaço antes do '(' faz de F uma macro de objeto
+    assert_eq!(expandir("#define F (x)\nF"), "( x )");
+    // sem '(' depois do nome, a macro de função não é expandida
+    assert_eq!(expandir("#define F(x) x\nF + 1"), "F + 1");
+    assert_eq!(expandir("#define N 1\n#undef N\nN"), "N");
+}
+
+#[test]
+fn reexame_expande_o_resultado() {
+    assert_eq!(expandir("#define A B\n#define B 1\nA"), "1");
+    assert_eq!(
+        expandir("#define SQ(x) ((x) * (x))\nSQ(SQ(2))"),
+        "( ( ( ( 2 ) * ( 2 ) ) ) * ( ( ( 2 ) * ( 2 ) ) ) )"
+    );
+    // o exemplo de C17 6.10.3.5
+    assert_eq!(
+        expandir("#define f(a) a*g\n#define g(a) f(a)\nf(2)(9)"),
+        "2 * 9 * g"
+    );
+}
+
+#[test]
+fn macro_nao_se_expande_dentro_de_si_mesma() {
+    assert_eq!(expandir("#define REC REC + 1\nREC"), "REC + 1");
+    assert_eq!(expandir("#define F G\n#define G F\nF G"), "F G");
+    assert_eq!(expandir("#define X(a) X(a + 1)\nX(0)"), "X ( 0 + 1 )");
+}
+
+#[test]
+fn stringificacao() {
+    assert_eq!(
+        expandir("#define STR(x) #x\nSTR(a + \"b\\n\"  c)"),
+        r#""a + \"b\\n\" c""#
+    );
+    // o argumento de '#' não é expandido antes; por isso XSTR passa por STR
+    assert_eq!(
+        expandir("#define N 10\n#define STR(x) #x\n#define XSTR(x) STR(x)\nSTR(N) XSTR(N)"),
+        r#""N" "10""#
+    );
+}
+
+#[test]
+fn colagem_de_tokens() {
+    let cat = "#define CAT(a, b) a ## b\n";
+    assert_eq!(expandir(&format!("{}CAT(var, 1)", cat)), "var1");
+    assert_eq!(expandir(&format!("{}CAT(1, 2)", cat)), "12");
+    assert_eq!(expandir(&format!("{}CAT(, z) CAT(,)", cat)), "z");
+    assert_eq!(expandir(&format!("{}CAT(<, <=)", cat)), "<<=");
+
+    // '0x' sozinho não é um número válido, mas é um pp-number que pode ser colado
+    assert_eq!(
+        expandir("#define HEX(x) 0x ## x\nHEX(FF) HEX(1p3)"),
+        "0xFF 0x1p3"
+    );
+    assert_eq!(expandir(&format!("{}CAT(1, e)", cat)), "1e");
+
+    let (_, diagnosticos) = processar(&mut Preprocessador::new(), &format!("{}CAT(+, /)", cat));
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::ColagemInvalida]);
+    let (_, diagnosticos) = processar(&mut Preprocessador::new(), "#define P(a) a ## 'b\nP(L)");
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::ColagemInvalida]);
+}
+
+#[test]
+fn argumentos_variadicos() {
+    let log = "#define LOG(fmt, ...) printf(fmt, __VA_ARGS__)\n";
+    assert_eq!(
+        expandir(&format!("{}LOG(\"%d %d\", 1, 2)", log)),
+        r#"printf ( "%d %d" , 1 , 2 )"#
+    );
+
+    // ', ## __VA_ARGS__' some com a vírgula quando não há argumentos variádicos
+    let elog = "#define ELOG(fmt, ...) printf(fmt, ## __VA_ARGS__)\n";
+    assert_eq!(
+        expandir(&format!("{}ELOG(\"oi\")", elog)),
+        r#"printf ( "oi" )"#
+    );
+    assert_eq!(
+        expandir(&format!("{}ELOG(\"oi %d\", 3)", elog)),
+        r#"printf ( "oi %d" , 3 )"#
+    );
+}
+
+#[test]
+fn quantidade_errada_de_argumentos() {
+    let sq = "#define SQ(x) ((x) * (x))\n";
+
+    let (_, diagnosticos) = processar(&mut Preprocessador::new(), &format!("{}SQ(1, 2)", sq));
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::ArgumentosMacro]);
+    assert_eq!(
+        diagnosticos[0].mensagem,
+        "Macro 'SQ' espera 1 argumento(s), mas recebeu 2"
+    );
+
+    let (_, diagnosticos) = processar(&mut Preprocessador::new(), &format!("{}SQ(1", sq));
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::ArgumentosMacro]);
+    assert_eq!(
+        diagnosticos[0].mensagem,
+        "Lista de argumentos da macro 'SQ' não foi fechada"
+    );
+}
+
+#[test]
+fn redefinicao_com_outro_corpo_e_aviso() {
+    let (saida, diagnosticos) = processar(
+        &mut Preprocessador::new(),
+        "#define N 1\n#define N 1\n#define N 2\nN",
+    );
+    assert_eq!(saida, "2");
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::MacroRedefinida]);
+    assert_eq!(diagnosticos[0].severidade, Severidade::Aviso);
+    assert_eq!(diagnosticos[0].span.linha, 3);
+}
+
+#[test]
+fn condicionais_aninhados() {
+    let codigo = "\
+#define DEBUG 2
+#define VER 3
+#if defined(DEBUG) && DEBUG > 1
+a
+#elif defined VER
+b
+#else
+c
+#endif
+#ifdef NADA
+d
+#if 1/0
+e
+#endif
+#elif VER == 3 && !defined(NADA2) && FOO == 0
+f
+#else
+g
+#endif
+#if (VER << 2) > 10 ? 1 : 0
+h
+#endif
+#if 0
+#error nunca
+nada
+#else
+i
+#endif
+#ifndef DEBUG
+j
+#endif
+";
+    assert_eq!(expandir(codigo), "a f h i");
+}
+
+#[test]
+fn if_usa_aritmetica_unsigned() {
+    assert_eq!(expandir("#if -1 > 0u\nsim\n#else\nnao\n#endif\n"), "sim");
+    assert_eq!(
+        expandir("#if 0xFFFFFFFFFFFFFFFF == -1\nsim\n#endif\n"),
+        "sim"
+    );
+    assert_eq!(expandir("#if -1 < 0\nsim\n#endif\n"), "sim");
+}
+
+#[test]
+fn regiao_desativada_nao_precisa_ser_lexicamente_valida() {
+    let codigo = "#if 0\nit's a note\n\"aberta\n'\\q'\n#endif\nint main(void){return 0;}\n";
+    assert_eq!(expandir(codigo), "int main ( void ) { return 0 ; }");
+
+    // números malformados também só dão erro fora da região desativada
+    let codigo = "#if 0\nversion 1.2.3\n09 0x 1e+\n#elif 0\n0b2\n#endif\nint x = 08;\n";
+    let (saida, diagnosticos) = processar(&mut Preprocessador::new(), codigo);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    assert_eq!(saida, "int x = 08 ;");
+
+    // numa região ativa, o '#if' reporta o número
+    let (_, diagnosticos) = processar(&mut Preprocessador::new(), "#if 09\n#endif\n");
+    assert_eq!(codigos(&diagnosticos), [CodigoErro::NumeroInvalido]);
+}
+
+#[test]
+fn erros_de_condicionais() {
+    let casos = [
+        ("#endif\n", "'#endif' sem '#if' correspondente"),
+        ("#else\n", "'#else' sem '#if' correspondente"),
+        ("#elif 1\n", "'#elif' sem '#if' correspondente"),
+        (
+            "#if 1\n#else\n#else\n#endif\n",
+            "'#else' depois do '#else' do mesmo '#if'",
+        ),
+        (
+            "#if 1\n",
+            "Diretiva condicional sem '#endif' correspondente",
+        ),
+        (
+            "#ifdef\n#endif\n",
+            "Esperado um único nome de macro após '#ifdef'",
+        ),
+    ];
+
+    for (codigo, mensagem) in casos {
+        let (_, diagnosticos) = processar(&mut Preprocessador::new(), codigo);
+        assert_eq!(diagnosticos.len(), 1, "{}: {:?}", codigo, diagnosticos);
+        assert_eq!(diagnosticos[0].mensagem, mensagem, "{}", codigo);
+    }
+}
+
+#[test]
+fn erros_na_expressao_do_if() {
+    let casos = [
+        "#if\n#endif\n",
+        "#if 1 2\n#endif\n",
+        "#if 1.5\n#endif\n",
+        "#if 1/0\n#endif\n",
+        "#if defined(\n#endif\n",
+        "#if (1\n#endif\n",
+    ];
+
+    for codigo in casos {
+        let (_, diagnosticos) = processar(&mut Preprocessador::new(), codigo);
+        assert_eq!(diagnosticos.len(), 1, "{}: {:?}", codigo, diagnosticos);
+        assert_eq!(diagnosticos[0].severidade, Severidade::Erro, "{}", codigo);
+    }
+}
+
+#[test]
+fn guarda_de_inclusao_e_pragma_once() {
+    let mut preprocessador = com_arquivos(&[
+        (
+            "a.h",
+            "#ifndef A_H\n#define A_H\n#define A 1\nint a;\n#endif\n",
+        ),
+        ("sub/b.h", "#include \"../a.h\"\n#define B 2\nint b;\n"),
+        ("once.h", "#pragma once\n#define ONCE 3\nint once;\n"),
+    ]);
+    let codigo = "\
+#include \"a.h\"
+#include \"a.h\"
+#include \"sub/b.h\"
+#include \"once.h\"
+#include \"once.h\"
+int x = A + B + ONCE;
+";
+    let (saida, diagnosticos) = processar(&mut preprocessador, codigo);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    // o '#pragma' segue para o Parser, que o transforma em Pragma
+    assert_eq!(
+        saida,
+        "int a ; int b ; #pragma once int once ; int x = 1 + 2 + 3 ;"
+    );
+}
+
+#[test]
+fn inclusao_circular_e_arquivo_faltando() {
+    let mut preprocessador = com_arquivos(&[
+        ("cic1.h", "#include \"cic2.h\"\n"),
+        ("cic2.h", "#include \"cic1.h\"\n"),
+    ]);
+    let (_, diagnosticos) = processar(
+        &mut preprocessador,
+        "#include \"cic1.h\"\n#include \"falta.h\"\n",
+    );
+
+    assert_eq!(
+        codigos(&diagnosticos),
+        [
+            CodigoErro::InclusaoCiclica,
+            CodigoErro::InclusaoNaoEncontrada
+        ]
+    );
+    assert_eq!(
+        diagnosticos[0].formatar(preprocessador.arquivos()),
+        "proj/cic2.h: erro[E0307] na linha 1, coluna 1: \
+         Inclusão circular: proj/cic1.h -> proj/cic2.h -> proj/cic1.h"
+    );
+    assert_eq!(diagnosticos[1].span.linha, 2);
+}
+
+#[test]
+fn caminhos_de_inclusao() {
+    let mut preprocessador = com_arquivos(&[("inc/x.h", "int x;\n"), ("sys/y.h", "int y;\n")]);
+    preprocessador.adicionar_caminho_usuario("proj/inc");
+    preprocessador.adicionar_caminho_sistema("proj/sys");
+
+    let (saida, diagnosticos) =
+        processar(&mut preprocessador, "#include \"x.h\"\n#include <y.h>\n");
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    assert_eq!(saida, "int x ; int y ;");
+}
+
+#[test]
+fn definicoes_da_linha_de_comando() {
+    let mut preprocessador = Preprocessador::new();
+    preprocessador.definir("NDEBUG").unwrap();
+    preprocessador.definir("N=10").unwrap();
+    preprocessador.definir("F(x)=x*2").unwrap();
+    preprocessador.definir("TEMP=0").unwrap();
+    preprocessador.remover_definicao("TEMP");
+    preprocessador.remover_definicao("__STDC__");
+
+    let (saida, diagnosticos) = processar(&mut preprocessador, "NDEBUG N F(3) TEMP __STDC__");
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    assert_eq!(saida, "1 10 3 * 2 TEMP __STDC__");
+
+    let erro = Preprocessador::new().definir("").unwrap_err();
+    assert_eq!(erro.codigo, CodigoErro::DiretivaInvalida);
+}
+
+#[test]
+fn macros_predefinidas() {
+    let codigo = "#define L __LINE__\nint a = __LINE__;\nint b = L;\n";
+    assert_eq!(expandir(codigo), "int a = 2 ; int b = 3 ;");
+    assert_eq!(expandir("__STDC__ __STDC_VERSION__"), "1 201710L");
+    assert_eq!(expandir("#ifdef __cplusplus\nerrado\n#endif"), "");
+
+    let mut preprocessador = Preprocessador::new();
+    preprocessador.definir_arquivo_principal("dir/main.c");
+    let (saida, _) = processar(&mut preprocessador, "__FILE__");
+    assert_eq!(saida, "\"dir/main.c\"");
+}
+
+#[test]
+fn line_muda_a_linha_e_o_arquivo() {
+    let codigo = "\
+int a;
+#line 100
+int b = __LINE__;
+#line 200 \"outro.c\"
+int c = __LINE__; const char *f = __FILE__;
+";
+    let mut preprocessador = Preprocessador::new();
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let (saida, diagnosticos) = preprocessador.processar_parcial(tokens);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    assert_eq!(
+        texto(&saida),
+        r#"int a ; int b = 100 ; int c = 200 ; const char * f = "outro.c" ;"#
+    );
+
+    let b = saida
+        .iter()
+        .find(|token| token.token == Token::Identificador("b".into()))
+        .unwrap();
+    assert_eq!(b.span.linha, 100);
+
+    let c = saida
+        .iter()
+        .find(|token| token.token == Token::Identificador("c".into()))
+        .unwrap();
+    assert_eq!(c.span.linha, 200);
+    assert_eq!(
+        preprocessador.arquivos()[c.span.arquivo].to_str(),
+        Some("outro.c")
+    );
+}
+
+#[test]
+fn error_e_warning() {
+    let codigo = "\
+#if 0
+#error nunca
+#endif
+#warning isto é só um \"aviso\"
+int a;
+#error \"don't\" compile
+int b;
+";
+    let (saida, diagnosticos) = processar(&mut Preprocessador::new(), codigo);
+    // '#error' encerra o processamento
+    assert_eq!(saida, "int a ;");
+    assert_eq!(
+        codigos(&diagnosticos),
+        [CodigoErro::DiretivaAviso, CodigoErro::DiretivaErro]
+    );
+    assert_eq!(diagnosticos[0].severidade, Severidade::Aviso);
+    assert_eq!(diagnosticos[0].mensagem, "#warning isto é só um \"aviso\"");
+    assert_eq!(diagnosticos[1].span.linha, 6);
+    assert_eq!(diagnosticos[1].mensagem, "#error \"don't\" compile");
+}
+
+#[test]
+fn grafias_alternativas_so_em_cpp() {
+    let codigo = "#if 1 and not 0\nsim\n#endif\n#define S(x) #x\nS(and) a or b bitor c";
+
+    let mut preprocessador = Preprocessador::new();
+    preprocessador.definir_dialeto(Dialeto::Cpp);
+    let (saida, diagnosticos) = processar(&mut preprocessador, codigo);
+    assert!(diagnosticos.is_empty(), "{:?}", diagnosticos);
+    // a stringificação guarda a grafia escrita
+    assert_eq!(saida, "sim \"and\" a || b | c");
+
+    // em C são nomes comuns
+    assert_eq!(expandir("a or b and c"), "a or b and c");
+}
@@ -0,0 +1,141 @@
+// a quebra de linha só é token dentro de uma diretiva, onde marca o fim dela; no
+// resto do código expressões e declarações podem ocupar várias linhas
+use CompiladorRustC::parser::{OperacaoPack, Operador};
+use CompiladorRustC::{ExprKind, Lexer, Parser, Pragma, Stmt, StmtKind, Token};
+
+fn analisar(codigo: &str) -> Vec<Stmt<'_>> {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+// as declarações do corpo da única função do código
+fn corpo(codigo: &str) -> Vec<Stmt<'_>> {
+    let mut programa = analisar(codigo);
+    let StmtKind::DeclaracaoFuncao { corpo, .. } = programa.remove(0).kind else {
+        panic!("esperada uma função");
+    };
+    let StmtKind::Bloco { declaracoes } = corpo.kind else {
+        panic!("{:?}", corpo);
+    };
+    declaracoes
+}
+
+#[test]
+fn chamada_com_argumentos_em_varias_linhas() {
+    let codigo = "\
+int main(void) {
+    printf(\"%d %d\\n\",
+           a,
+           b
+    );
+}
+";
+    let declaracoes = corpo(codigo);
+    assert_eq!(declaracoes.len(), 1, "{:?}", declaracoes);
+    let StmtKind::Expressao(expr) = &declaracoes[0].kind else {
+        panic!("{:?}", declaracoes[0]);
+    };
+    let ExprKind::ChamadaFuncao { argumentos, .. } = &expr.kind else {
+        panic!("{:?}", expr);
+    };
+    assert_eq!(argumentos.len(), 3);
+    assert_eq!(declaracoes[0].span.linha, 2);
+}
+
+#[test]
+fn condicao_e_declaracao_em_varias_linhas() {
+    let codigo = "\
+int f(int a, int b) {
+    if (a > 0 &&
+        b > 0)
+        return
+            a +
+            b;
+    int
+        x
+        =
+        1;
+    return x;
+}
+";
+    let declaracoes = corpo(codigo);
+    assert_eq!(declaracoes.len(), 3, "{:?}", declaracoes);
+
+    let StmtKind::If { condicao, .. } = &declaracoes[0].kind else {
+        panic!("{:?}", declaracoes[0]);
+    };
+    assert!(matches!(
+        condicao.kind,
+        ExprKind::Binario {
+            operador: Operador::EComercialDuplo,
+            ..
+        }
+    ));
+    assert!(matches!(
+        declaracoes[1].kind,
+        StmtKind::DeclaracaoVariavel { .. }
+    ));
+    assert_eq!(declaracoes[2].span.linha, 11);
+}
+
+#[test]
+fn so_a_diretiva_gera_quebra_de_linha() {
+    let tokens = Lexer::new("#pragma once\nint\nx;\n#define N 1\n\nint y;\n")
+        .tokenizar()
+        .unwrap();
+    let quebras = tokens
+        .iter()
+        .filter(|token| token.token == Token::QuebraLinha)
+        .count();
+    assert_eq!(quebras, 2);
+}
+
+#[test]
+fn diretiva_termina_na_quebra_de_linha() {
+    let codigo = "\
+#pragma pack(push, 1)
+struct S { char c; };
+#ident \"versao\"
+int y;
+#pragma pack(pop)";
+    let programa = analisar(codigo);
+    assert_eq!(programa.len(), 5, "{:?}", programa);
+
+    assert!(matches!(
+        programa[0].kind,
+        StmtKind::Pragma(Pragma::Pack {
+            operacao: OperacaoPack::Empilhar,
+            alinhamento: Some(1),
+        })
+    ));
+    assert!(matches!(programa[1].kind, StmtKind::Struct { .. }));
+    assert!(matches!(&programa[2].kind, StmtKind::Diretiva(comando) if comando == "ident"));
+    assert!(matches!(
+        programa[3].kind,
+        StmtKind::DeclaracaoVariavel { .. }
+    ));
+    // a última diretiva termina no fim do arquivo, sem quebra de linha
+    assert!(matches!(
+        programa[4].kind,
+        StmtKind::Pragma(Pragma::Pack {
+            operacao: OperacaoPack::Desempilhar,
+            ..
+        })
+    ));
+
+    // a diretiva não leva o código da linha seguinte
+    assert_eq!(programa[0].span.linha, 1);
+    assert_eq!(programa[1].span.linha, 2);
+    assert_eq!(programa[3].span.linha, 4);
+}
+
+#[test]
+fn diretiva_dentro_de_funcao() {
+    let declaracoes = corpo("int f(void) {\n    int x = 1;\n#pragma once\n    return x;\n}\n");
+    assert_eq!(declaracoes.len(), 3, "{:?}", declaracoes);
+    assert!(matches!(
+        declaracoes[1].kind,
+        StmtKind::Pragma(Pragma::Once)
+    ));
+    assert!(matches!(declaracoes[2].kind, StmtKind::Retorno(_)));
+}
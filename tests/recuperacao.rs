@@ -0,0 +1,84 @@
+// recuperação do Parser: um erro não encerra a análise, cada um vira diagnóstico e a
+// árvore parcial marca o lugar com StmtKind::Erro
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::{Diagnostic, Lexer, Parser, Stmt, StmtKind};
+
+fn analisar(codigo: &str) -> (Vec<Stmt<'_>>, Vec<Diagnostic>) {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    Parser::new(tokens).parse_parcial()
+}
+
+fn linhas(diagnosticos: &[Diagnostic]) -> Vec<usize> {
+    diagnosticos
+        .iter()
+        .map(|diagnostico| diagnostico.span.linha)
+        .collect()
+}
+
+#[test]
+fn reporta_todos_os_erros_do_arquivo() {
+    let codigo = "\
+int a = ;
+int b = 2;
+float c = (1 + ;
+int d = 4;
+int e = 5 5;
+";
+    let (stmts, diagnosticos) = analisar(codigo);
+
+    assert_eq!(linhas(&diagnosticos), [1, 3, 5], "{:?}", diagnosticos);
+    assert_eq!(
+        diagnosticos[2].codigo,
+        CodigoErro::PontoVirgulaEsperado,
+        "{:?}",
+        diagnosticos[2]
+    );
+
+    let erros: Vec<bool> = stmts
+        .iter()
+        .map(|stmt| matches!(stmt.kind, StmtKind::Erro))
+        .collect();
+    assert_eq!(erros, [true, false, true, false, true]);
+}
+
+#[test]
+fn erro_dentro_de_funcao_nao_derruba_o_resto() {
+    let codigo = "\
+int f(void) {
+    int x = ;
+    x = 1;
+    if (x { }
+    return x;
+}
+int g(void) { return 0; }
+";
+    let (stmts, diagnosticos) = analisar(codigo);
+
+    assert_eq!(linhas(&diagnosticos), [2, 4], "{:?}", diagnosticos);
+    assert_eq!(stmts.len(), 2, "{:?}", stmts);
+
+    let StmtKind::DeclaracaoFuncao { corpo, .. } = &stmts[0].kind else {
+        panic!("{:?}", stmts[0]);
+    };
+    let StmtKind::Bloco { declaracoes } = &corpo.kind else {
+        panic!("{:?}", corpo);
+    };
+    assert!(matches!(declaracoes[0].kind, StmtKind::Erro));
+    assert!(matches!(declaracoes[1].kind, StmtKind::Expressao(_)));
+    assert!(matches!(
+        declaracoes.last().unwrap().kind,
+        StmtKind::Retorno(_)
+    ));
+
+    assert!(matches!(stmts[1].kind, StmtKind::DeclaracaoFuncao { .. }));
+}
+
+#[test]
+fn parse_devolve_todos_os_diagnosticos() {
+    let tokens = Lexer::new("int a = ;\nint b = ;\n").tokenizar().unwrap();
+    let diagnosticos = Parser::new(tokens).parse().unwrap_err();
+    assert_eq!(linhas(&diagnosticos), [1, 2]);
+
+    let tokens = Lexer::new("int a = 1;").tokenizar().unwrap();
+    assert!(Parser::new(tokens).parse().is_ok());
+}
@@ -0,0 +1,198 @@
+// a árvore de Tipo montada pelos especificadores e declaradores: ponteiros da frente
+// primeiro, depois os sufixos de dentro para fora, depois o declarador entre parênteses
+use CompiladorRustC::diagnostico::CodigoErro;
+use CompiladorRustC::parser::OperandoSizeof;
+use CompiladorRustC::tipo::{Qualificadores, Sinal, TipoBase};
+use CompiladorRustC::{ExprKind, Lexer, Parser, StmtKind, Tipo};
+
+// o tipo do primeiro declarador, lido de fora para dentro como em
+// 'ponteiro para função(int) retornando int'
+fn tipo(codigo: &str) -> String {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let programa = Parser::new(tokens).parse().unwrap();
+    let StmtKind::DeclaracaoVariavel { declaradores, .. } = &programa[0].kind else {
+        panic!("{:?}", programa);
+    };
+    descrever(&declaradores[0].tipo)
+}
+
+fn erro(codigo: &str) -> CodigoErro {
+    let tokens = Lexer::new(codigo).tokenizar().unwrap();
+    let diagnosticos = Parser::new(tokens).parse().unwrap_err();
+    diagnosticos[0].codigo
+}
+
+fn qualificadores(qualificadores: &Qualificadores) -> String {
+    let mut texto = String::new();
+    if qualificadores.constante {
+        texto.push_str("const ");
+    }
+    if qualificadores.volatil {
+        texto.push_str("volatile ");
+    }
+    texto
+}
+
+fn descrever(tipo: &Tipo) -> String {
+    match tipo {
+        Tipo::Base {
+            base,
+            sinal,
+            qualificadores: q,
+            ..
+        } => {
+            let sinal = match sinal {
+                Some(Sinal::Signed) => "signed ",
+                Some(Sinal::Unsigned) => "unsigned ",
+                None => "",
+            };
+            let base = match base {
+                TipoBase::Void => "void".to_string(),
+                TipoBase::Char => "char".to_string(),
+                TipoBase::Short => "short".to_string(),
+                TipoBase::Int => "int".to_string(),
+                TipoBase::Long => "long".to_string(),
+                TipoBase::LongLong => "long long".to_string(),
+                TipoBase::Float => "float".to_string(),
+                TipoBase::Double => "double".to_string(),
+                TipoBase::LongDouble => "long double".to_string(),
+                TipoBase::Bool => "_Bool".to_string(),
+                TipoBase::Nomeado(nome) => nome.clone(),
+                outro => format!("{:?}", outro),
+            };
+            format!("{}{}{}", qualificadores(q), sinal, base)
+        }
+        Tipo::Ponteiro {
+            apontado,
+            qualificadores: q,
+        } => format!("{}ponteiro para {}", qualificadores(q), descrever(apontado)),
+        Tipo::Array { elemento, tamanho } => {
+            let tamanho = match tamanho.as_ref().map(|tamanho| &tamanho.kind) {
+                Some(ExprKind::NumeroInt(tamanho)) => tamanho.to_string(),
+                Some(outro) => panic!("{:?}", outro),
+                None => String::new(),
+            };
+            format!("array[{}] de {}", tamanho, descrever(elemento))
+        }
+        Tipo::Funcao {
+            retorno,
+            parametros,
+            variadica,
+        } => {
+            let mut parametros: Vec<String> = parametros
+                .iter()
+                .map(|parametro| descrever(&parametro.tipo))
+                .collect();
+            if *variadica {
+                parametros.push("...".to_string());
+            }
+            format!(
+                "função({}) retornando {}",
+                parametros.join(", "),
+                descrever(retorno)
+            )
+        }
+    }
+}
+
+#[test]
+fn especificadores() {
+    let casos = [
+        ("int x;", "int"),
+        ("unsigned u;", "unsigned int"),
+        ("long int l;", "long"),
+        ("unsigned long long int ull;", "unsigned long long"),
+        ("short unsigned s;", "unsigned short"),
+        ("signed char c;", "signed char"),
+        ("long double d;", "long double"),
+        ("int const volatile x;", "const volatile int"),
+        (
+            "const unsigned long *p;",
+            "ponteiro para const unsigned long",
+        ),
+    ];
+
+    for (codigo, esperado) in casos {
+        assert_eq!(tipo(codigo), esperado, "{}", codigo);
+    }
+}
+
+#[test]
+fn declaradores_derivados() {
+    let casos = [
+        ("int *a[3];", "array[3] de ponteiro para int"),
+        ("int (*a)[3];", "ponteiro para array[3] de int"),
+        ("int a[2][3];", "array[2] de array[3] de int"),
+        ("int v[];", "array[] de int"),
+        ("int **pp;", "ponteiro para ponteiro para int"),
+        ("char *const p;", "const ponteiro para char"),
+        (
+            "const char *const *p;",
+            "ponteiro para const ponteiro para const char",
+        ),
+        ("int (*f)(int);", "ponteiro para função(int) retornando int"),
+        (
+            "int *f(int, char *);",
+            "função(int, ponteiro para char) retornando ponteiro para int",
+        ),
+        (
+            "int printf(const char *, ...);",
+            "função(ponteiro para const char, ...) retornando int",
+        ),
+        ("int f(void);", "função() retornando int"),
+        (
+            "int (*tabela[4])(void);",
+            "array[4] de ponteiro para função() retornando int",
+        ),
+        (
+            "void (*signal(int, void (*)(int)))(int);",
+            "função(int, ponteiro para função(int) retornando void) retornando ponteiro para função(int) retornando void",
+        ),
+        (
+            "int *(*f)(int)[3];",
+            "ponteiro para função(int) retornando array[3] de ponteiro para int",
+        ),
+    ];
+
+    for (codigo, esperado) in casos {
+        assert_eq!(tipo(codigo), esperado, "{}", codigo);
+    }
+}
+
+#[test]
+fn tipo_sem_nome_em_cast_e_sizeof() {
+    let tokens = Lexer::new("(const char **)p").tokenizar().unwrap();
+    let expressao = Parser::new(tokens).parse_virgula().unwrap();
+    let ExprKind::Cast { tipo, .. } = &expressao.kind else {
+        panic!("{:?}", expressao);
+    };
+    assert_eq!(descrever(tipo), "ponteiro para ponteiro para const char");
+
+    let tokens = Lexer::new("sizeof(int (*)[4])").tokenizar().unwrap();
+    let expressao = Parser::new(tokens).parse_virgula().unwrap();
+    let ExprKind::Sizeof(OperandoSizeof::Tipo(tipo)) = &expressao.kind else {
+        panic!("{:?}", expressao);
+    };
+    assert_eq!(descrever(tipo), "ponteiro para array[4] de int");
+}
+
+#[test]
+fn combinacoes_invalidas() {
+    let casos = [
+        "unsigned float x;",
+        "signed double x;",
+        "unsigned void x;",
+        "long char x;",
+        "short long x;",
+        "long long long x;",
+        "short double x;",
+        "int float x;",
+        "char int x;",
+        "unsigned signed x;",
+        "static extern int x;",
+    ];
+
+    for codigo in casos {
+        assert_eq!(erro(codigo), CodigoErro::TipoInvalido, "{}", codigo);
+    }
+}
@@ -0,0 +1,98 @@
+// tokenizar_com_trivia seguido de reconstruir_fonte devolve a fonte byte a byte,
+// e a trivia de cada token fica do lado certo
+use CompiladorRustC::token::TipoTrivia;
+use CompiladorRustC::{Lexer, Token, TokenComTrivia, reconstruir_fonte};
+
+fn com_trivia(codigo: &str) -> Vec<TokenComTrivia<'_>> {
+    Lexer::new(codigo).tokenizar_com_trivia().unwrap()
+}
+
+fn tipos(trivia: &[CompiladorRustC::Trivia]) -> Vec<TipoTrivia> {
+    trivia.iter().map(|trivia| trivia.tipo).collect()
+}
+
+#[test]
+fn reconstroi_a_fonte_byte_a_byte() {
+    let casos = [
+        "",
+        "int x;",
+        "int main(void) {\r\n    return 0;\r\n}\r\n",
+        "int x = 1 \\\n + 2;\n",
+        "int x = 1 \\\r\n + 2;\r\n",
+        "/* antes */ int /* no meio */ x; // depois\n// sozinho\n",
+        "/* várias\n   linhas */\nint y;",
+        "#include <stdio.h>\n#define N 10 // comentário\nint v[N];\n",
+        "int x;   \t",
+        "int x;\n\n\n",
+        "\t\n  \r\n",
+        "char *s = \"a\\tb\"; char c = '\\n';",
+        "a<:0:> <% %> %:",
+    ];
+
+    for codigo in casos {
+        assert_eq!(
+            reconstruir_fonte(&com_trivia(codigo)),
+            codigo,
+            "{:?}",
+            codigo
+        );
+    }
+}
+
+#[test]
+fn trivia_ate_o_fim_da_linha_fica_com_o_token_anterior() {
+    let tokens = com_trivia("int x; // fim\n/* bloco */ y\r\n");
+    let textos: Vec<&str> = tokens.iter().map(|token| token.texto).collect();
+    assert_eq!(textos, ["int", "x", ";", "y", ""]);
+
+    let ponto_virgula = &tokens[2];
+    assert_eq!(
+        tipos(&ponto_virgula.depois),
+        [
+            TipoTrivia::Espaco,
+            TipoTrivia::ComentarioLinha,
+            TipoTrivia::QuebraLinha
+        ]
+    );
+
+    let y = &tokens[3];
+    assert_eq!(
+        tipos(&y.antes),
+        [TipoTrivia::ComentarioBloco, TipoTrivia::Espaco]
+    );
+    assert_eq!(tipos(&y.depois), [TipoTrivia::QuebraLinha]);
+    assert_eq!(y.depois[0].texto, "\r\n");
+}
+
+#[test]
+fn continuacao_entre_tokens_vira_trivia() {
+    let tokens = com_trivia("a\\\n b");
+    assert_eq!(tokens[1].token.token, Token::Identificador("b".into()));
+    assert_eq!(
+        tipos(&tokens[0].depois),
+        [TipoTrivia::Continuacao, TipoTrivia::Espaco]
+    );
+    assert_eq!(tokens[0].depois[0].texto, "\\\n");
+    assert_eq!(tokens[0].depois[0].span.linha, 1);
+    assert_eq!(tokens[0].depois[0].span.coluna, 2);
+
+    // no meio dos espaços, a continuação fica no texto deles
+    let tokens = com_trivia("a \\\r\n b");
+    assert_eq!(tipos(&tokens[0].depois), [TipoTrivia::Espaco]);
+    assert_eq!(tokens[0].depois[0].texto, " \\\r\n ");
+}
+
+#[test]
+fn espacos_no_fim_do_arquivo_ficam_antes_do_fundo() {
+    let tokens = com_trivia("x  \t");
+    let fundo = tokens.last().unwrap();
+    assert_eq!(fundo.token.token, Token::Fundo);
+    assert_eq!(fundo.texto, "");
+
+    let espacos: String = tokens
+        .iter()
+        .flat_map(|token| token.antes.iter().chain(&token.depois))
+        .map(|trivia| trivia.texto)
+        .collect();
+    assert_eq!(espacos, "  \t");
+}